@@ -0,0 +1,57 @@
+//! Times `insert` and `find_all` on a table with 30 columns
+//!
+//! Run with `cargo bench --bench find_all_wide`. Every stored record is also
+//! checked against the one read back, so the mapping stays correct while it
+//! gets faster.
+
+use std::time::Instant;
+
+use kingler::{Kingler, Table};
+use serde::{Deserialize, Serialize};
+
+const ROWS: i64 = 2_000;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Table)]
+struct Wide {
+    id: Option<i64>,
+    c01: i64, c02: String, c03: f64, c04: bool, c05: i64,
+    c06: String, c07: f64, c08: bool, c09: i64, c10: String,
+    c11: f64, c12: bool, c13: i64, c14: String, c15: f64,
+    c16: bool, c17: i64, c18: String, c19: f64, c20: bool,
+    c21: i64, c22: String, c23: f64, c24: bool, c25: i64,
+    c26: String, c27: f64, c28: bool, c29: Option<i64>,
+}
+
+fn record(i: i64) -> Wide {
+    let text = |n: i64| format!("value {}", i * 100 + n);
+    let real = |n: i64| (i * 100 + n) as f64 / 4.0;
+    Wide {
+        id: Some(i),
+        c01: i, c02: text(2), c03: real(3), c04: i % 2 == 0, c05: i * 5,
+        c06: text(6), c07: real(7), c08: i % 3 == 0, c09: i * 9, c10: text(10),
+        c11: real(11), c12: i % 5 == 0, c13: i * 13, c14: text(14), c15: real(15),
+        c16: i % 7 == 0, c17: i * 17, c18: text(18), c19: real(19), c20: i % 11 == 0,
+        c21: i * 21, c22: text(22), c23: real(23), c24: i % 13 == 0, c25: i * 25,
+        c26: text(26), c27: real(27), c28: i % 17 == 0, c29: (i % 2 == 0).then_some(i),
+    }
+}
+
+fn main() {
+    let path = std::env::temp_dir().join("kingler_bench_find_all_wide.db");
+    let _ = std::fs::remove_file(&path);
+    let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    db.create_table(record(0)).unwrap();
+    assert_eq!(Wide::columns().len(), 30);
+
+    let records: Vec<Wide> = (1..=ROWS).map(record).collect();
+    let start = Instant::now();
+    db.insert_many(&records).unwrap();
+    println!("insert_many: {} rows in {:?}", ROWS, start.elapsed());
+
+    let start = Instant::now();
+    let stored = db.find_all::<Wide>().unwrap();
+    println!("find_all:    {} rows in {:?}", ROWS, start.elapsed());
+
+    assert_eq!(stored, records);
+    let _ = std::fs::remove_file(&path);
+}
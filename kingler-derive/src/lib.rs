@@ -0,0 +1,395 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitBool, LitInt, LitStr, Type};
+
+/// Derives `kingler::Table` for a struct with named fields
+///
+/// The table is named after the struct and every field becomes a column whose
+/// SQL type is inferred from the Rust type. Fields can be tuned with the
+/// `#[column(...)]` attribute:
+/// * `primary_key` - Makes the column the primary key instead of the `id` field
+/// * `autoincrement = false` - Renders an INTEGER primary key without `AUTOINCREMENT`
+/// * `unique` - Adds a `UNIQUE` constraint to the column
+/// * `collate = "nocase"` - Sets the column collation (`nocase`, `binary` or `rtrim`)
+/// * `enum_text` - Stores an enum as the TEXT name of its variant and rejects
+///   unknown variant names when reading it back
+/// * `max_length = 255` - Limits a text column, rendered as `VARCHAR(255)` on
+///   backends that support it
+/// * `json` - Stores the field as a JSON document in a TEXT column
+/// * `generated = "price * quantity"` - Computes the column from an SQL
+///   expression instead of writing it, add `stored` to store the result
+/// * `comment = "..."` - Describes the column, read back by `Kingler::describe_table`
+///
+/// The struct itself accepts `#[table(...)]`:
+/// * `unique(a, b)` - Adds a constraint requiring the combination of the listed
+///   fields to be unique, repeated for several constraints
+/// * `without_rowid` - Creates the table `WITHOUT ROWID`, clustered on its
+///   primary key, which must be declared and can't use `AUTOINCREMENT`
+#[proc_macro_derive(Table, attributes(column, table))]
+pub fn derive_table(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Options collected from a field's `#[column(...)]` attributes
+#[derive(Default)]
+struct ColumnAttrs {
+    primary_key: bool,
+    autoincrement: Option<bool>,
+    unique: bool,
+    collate: Option<String>,
+    enum_text: bool,
+    max_length: Option<u32>,
+    json: bool,
+    generated: Option<String>,
+    stored: bool,
+    comment: Option<String>,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let table_name = name.to_string();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "Table can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "Table can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut parsed = Vec::new();
+    for field in fields {
+        parsed.push((field, parse_column_attrs(field)?));
+    }
+
+    // An explicit `#[column(primary_key)]` wins over the conventional `id` field
+    let explicit_keys: Vec<_> = parsed.iter().filter(|(_, attrs)| attrs.primary_key).collect();
+    if explicit_keys.len() > 1 {
+        return Err(syn::Error::new_spanned(
+            &explicit_keys[1].0.ident,
+            "only one field can be the primary key",
+        ));
+    }
+    let primary_key = match explicit_keys.first() {
+        Some((field, _)) => field.ident.as_ref().map(|ident| ident.to_string()),
+        None => parsed
+            .iter()
+            .filter_map(|(field, _)| field.ident.as_ref().map(|ident| ident.to_string()))
+            .find(|name| name == "id"),
+    };
+
+    let table_attrs = parse_table_attrs(&input, fields)?;
+    if table_attrs.without_rowid {
+        let Some(primary_key) = &primary_key else {
+            return Err(syn::Error::new_spanned(
+                name,
+                "without_rowid tables need a primary key",
+            ));
+        };
+        let (field, attrs) = parsed
+            .iter()
+            .find(|(field, _)| field.ident.as_ref().is_some_and(|ident| ident == primary_key))
+            .expect("primary key field");
+        if sql_type(&field.ty) == "Integer" && attrs.autoincrement != Some(false) {
+            return Err(syn::Error::new_spanned(
+                &field.ident,
+                "without_rowid tables can't use AUTOINCREMENT, add `autoincrement = false`",
+            ));
+        }
+    }
+
+    let mut columns = Vec::new();
+    for (field, attrs) in parsed {
+        let ident = field.ident.as_ref().expect("named field");
+        let column_name = ident.to_string();
+        let sql_type = if attrs.enum_text || attrs.json { "Text" } else { sql_type(&field.ty) };
+
+        let sql_type_variant = syn::Ident::new(sql_type, proc_macro2::Span::call_site());
+        let mut column = quote! {
+            ::kingler::Column::new(#column_name, ::kingler::SqlType::#sql_type_variant)
+        };
+        if primary_key.as_deref() == Some(column_name.as_str()) {
+            column = quote! { #column.primary_key() };
+        }
+        if let Some(autoincrement) = attrs.autoincrement {
+            if primary_key.as_deref() != Some(column_name.as_str()) {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "autoincrement only applies to the primary key",
+                ));
+            }
+            column = quote! { #column.autoincrement(#autoincrement) };
+        }
+        if attrs.unique {
+            column = quote! { #column.unique() };
+        }
+        if let Some(collate) = attrs.collate {
+            column = quote! { #column.collate(#collate) };
+        }
+        if let Some(max_length) = attrs.max_length {
+            if sql_type != "Text" {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "max_length only applies to text columns",
+                ));
+            }
+            column = quote! { #column.max_length(#max_length) };
+        }
+        if attrs.enum_text {
+            let ty = &field.ty;
+            column = quote! {
+                #column.enum_text(|value| {
+                    ::kingler::__private::serde_json::from_value::<#ty>(value.clone()).is_ok()
+                })
+            };
+        }
+        if sql_type == "Text" && is_big_integer(&field.ty) && !attrs.enum_text && !attrs.json {
+            column = quote! { #column.big_integer() };
+        }
+        if let Some(expression) = &attrs.generated {
+            let stored = attrs.stored;
+            column = quote! { #column.generated(#expression, #stored) };
+        } else if attrs.stored {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "stored only applies to generated columns",
+            ));
+        }
+        if attrs.json {
+            if attrs.enum_text {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "json and enum_text can't be combined",
+                ));
+            }
+            column = quote! { #column.json() };
+        }
+        if let Some(comment) = &attrs.comment {
+            column = quote! { #column.comment(#comment) };
+        }
+        columns.push(column);
+    }
+
+    let unique_constraints_fn = (!table_attrs.unique.is_empty()).then(|| {
+        let constraints = table_attrs.unique.iter().map(|columns| quote! { vec![#(#columns),*] });
+        quote! {
+            fn unique_constraints() -> Vec<Vec<&'static str>> {
+                vec![#(#constraints),*]
+            }
+        }
+    });
+
+    let without_rowid_fn = table_attrs.without_rowid.then(|| {
+        quote! {
+            fn without_rowid() -> bool {
+                true
+            }
+        }
+    });
+
+    let primary_key_fn = primary_key.map(|primary_key| {
+        quote! {
+            fn primary_key() -> &'static str {
+                #primary_key
+            }
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::kingler::Table for #name #ty_generics #where_clause {
+            fn table_name() -> &'static str {
+                #table_name
+            }
+
+            fn to_columns(&self) -> Vec<String> {
+                <Self as ::kingler::Table>::columns()
+                    .into_iter()
+                    .map(|column| column.name.to_string())
+                    .collect()
+            }
+
+            fn columns() -> Vec<::kingler::Column> {
+                vec![#(#columns),*]
+            }
+
+            #primary_key_fn
+
+            #unique_constraints_fn
+
+            #without_rowid_fn
+        }
+    })
+}
+
+/// Options collected from the struct's `#[table(...)]` attributes
+#[derive(Default)]
+struct TableAttrs {
+    unique: Vec<Vec<String>>,
+    without_rowid: bool,
+}
+
+fn parse_table_attrs(
+    input: &DeriveInput,
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::token::Comma>,
+) -> syn::Result<TableAttrs> {
+    let mut attrs = TableAttrs::default();
+    for attr in &input.attrs {
+        if !attr.path().is_ident("table") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("without_rowid") {
+                attrs.without_rowid = true;
+                return Ok(());
+            }
+            if !meta.path.is_ident("unique") {
+                return Err(meta.error("unsupported table attribute"));
+            }
+            let mut columns = Vec::new();
+            meta.parse_nested_meta(|column| {
+                let ident = column.path.require_ident()?;
+                if !fields.iter().any(|field| field.ident.as_ref() == Some(ident)) {
+                    return Err(column.error(format!("no field named `{}`", ident)));
+                }
+                columns.push(ident.to_string());
+                Ok(())
+            })?;
+            if columns.is_empty() {
+                return Err(meta.error("unique needs at least one field"));
+            }
+            attrs.unique.push(columns);
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+fn parse_column_attrs(field: &syn::Field) -> syn::Result<ColumnAttrs> {
+    let mut attrs = ColumnAttrs::default();
+    for attr in &field.attrs {
+        if !attr.path().is_ident("column") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("primary_key") {
+                attrs.primary_key = true;
+                Ok(())
+            } else if meta.path.is_ident("autoincrement") {
+                let value: LitBool = meta.value()?.parse()?;
+                attrs.autoincrement = Some(value.value);
+                Ok(())
+            } else if meta.path.is_ident("unique") {
+                attrs.unique = true;
+                Ok(())
+            } else if meta.path.is_ident("enum_text") {
+                attrs.enum_text = true;
+                Ok(())
+            } else if meta.path.is_ident("generated") {
+                let value: LitStr = meta.value()?.parse()?;
+                attrs.generated = Some(value.value());
+                Ok(())
+            } else if meta.path.is_ident("comment") {
+                let value: LitStr = meta.value()?.parse()?;
+                attrs.comment = Some(value.value());
+                Ok(())
+            } else if meta.path.is_ident("stored") {
+                attrs.stored = true;
+                Ok(())
+            } else if meta.path.is_ident("json") {
+                attrs.json = true;
+                Ok(())
+            } else if meta.path.is_ident("max_length") {
+                let value: LitInt = meta.value()?.parse()?;
+                attrs.max_length = Some(value.base10_parse()?);
+                Ok(())
+            } else if meta.path.is_ident("collate") {
+                let value: LitStr = meta.value()?.parse()?;
+                let collate = value.value().to_uppercase();
+                match collate.as_str() {
+                    "NOCASE" | "BINARY" | "RTRIM" => {
+                        attrs.collate = Some(collate);
+                        Ok(())
+                    }
+                    _ => Err(syn::Error::new_spanned(
+                        value,
+                        "collate must be one of \"nocase\", \"binary\" or \"rtrim\"",
+                    )),
+                }
+            } else {
+                Err(meta.error("unsupported column attribute"))
+            }
+        })?;
+    }
+    Ok(attrs)
+}
+
+/// Maps a Rust field type to the `kingler::SqlType` variant of its column
+///
+/// `Option<T>` maps to the type of `T`; anything unrecognised is stored as text.
+fn sql_type(ty: &Type) -> &'static str {
+    let Type::Path(path) = ty else {
+        return "Text";
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return "Text";
+    };
+    match segment.ident.to_string().as_str() {
+        "Option" => match inner_type(segment) {
+            Some(inner) => sql_type(inner),
+            None => "Text",
+        },
+        "Vec" => match inner_type(segment) {
+            Some(Type::Path(inner)) if inner.path.is_ident("u8") => "Blob",
+            _ => "Text",
+        },
+        "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => {
+            "Integer"
+        }
+        "f32" | "f64" => "Real",
+        "bool" => "Boolean",
+        "NaiveDateTime" | "DateTime" | "OffsetDateTime" | "PrimitiveDateTime" => "Datetime",
+        _ => "Text",
+    }
+}
+
+/// Whether the type is a 128-bit integer, possibly optional
+fn is_big_integer(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return false;
+    };
+    match segment.ident.to_string().as_str() {
+        "Option" => inner_type(segment).is_some_and(is_big_integer),
+        "i128" | "u128" => true,
+        _ => false,
+    }
+}
+
+fn inner_type(segment: &syn::PathSegment) -> Option<&Type> {
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
@@ -0,0 +1,162 @@
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::sqlite::Sqlite;
+use crate::{Kingler, KinglerError};
+
+/// A BLOB value opened for streaming with [`Kingler::open_blob`]
+///
+/// Reads and writes go straight to the database in chunks through SQLite's
+/// incremental BLOB I/O, so a large value never has to be held in memory. The
+/// size of the value is fixed when it is stored: writing can't grow it, so a
+/// value meant to be streamed in is stored first as `zeroblob(n)` with its size.
+pub struct Blob {
+    /// Connection the value is read and written through
+    sqlite: Sqlite,
+    table_name: String,
+    column: String,
+    rowid: i64,
+    /// Size of the value in bytes
+    len: usize,
+    /// Offset the next read or write starts at
+    position: usize,
+}
+
+impl Blob {
+    /// Size of the value in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the value is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Runs an operation on a handle opened at the stored position
+    fn with_handle<R>(
+        &self,
+        f: impl FnOnce(&mut rusqlite::blob::Blob<'_>) -> Result<R, rusqlite::Error>,
+    ) -> io::Result<R> {
+        let mut handle = self.sqlite
+            .blob_open(&self.table_name, &self.column, self.rowid, false)
+            .map_err(io::Error::other)?;
+        f(&mut handle).map_err(io::Error::other)
+    }
+}
+
+impl Read for Blob {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let position = self.position;
+        let read = self.with_handle(|handle| handle.read_at(buf, position))?;
+        self.position += read;
+        Ok(read)
+    }
+}
+
+impl Write for Blob {
+    /// Writes at the current position, stopping at the end of the value
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let position = self.position;
+        let len = buf.len().min(self.len.saturating_sub(position));
+        if len > 0 {
+            self.with_handle(|handle| handle.write_at(&buf[..len], position))?;
+        }
+        self.position += len;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for Blob {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => i64::try_from(offset).ok(),
+            SeekFrom::End(offset) => (self.len as i64).checked_add(offset),
+            SeekFrom::Current(offset) => (self.position as i64).checked_add(offset),
+        };
+        match position {
+            Some(position) if position >= 0 => {
+                self.position = position as usize;
+                Ok(position as u64)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+impl Kingler {
+    /// Opens a BLOB value for streaming reads and writes
+    ///
+    /// The table and column names are used as given, without the configured
+    /// table prefix.
+    ///
+    /// # Arguments
+    /// * `table` - Name of the table holding the value
+    /// * `column` - Name of the BLOB column
+    /// * `rowid` - Rowid of the record, which is its id for INTEGER primary keys
+    ///
+    /// # Errors
+    /// Returns `KinglerError::InvalidIdentifier` if a name isn't a valid
+    /// identifier and a database error if the record or column doesn't exist.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use std::io::{Read, Write};
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Attachment {
+    ///     id: Option<i64>,
+    ///     data: Vec<u8>,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_open_blob.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Attachment { id: None, data: Vec::new() }).unwrap();
+    ///
+    /// // Reserve 4 MiB for the value, then stream it in 64 KiB chunks
+    /// const SIZE: usize = 4 * 1024 * 1024;
+    /// let conn = rusqlite::Connection::open(&path).unwrap();
+    /// conn.execute("INSERT INTO Attachment (data) VALUES (zeroblob(?))", [SIZE]).unwrap();
+    /// let id = conn.last_insert_rowid();
+    ///
+    /// let chunk: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+    /// let mut blob = db.open_blob("Attachment", "data", id).unwrap();
+    /// assert_eq!(blob.len(), SIZE);
+    /// for _ in 0..SIZE / chunk.len() {
+    ///     blob.write_all(&chunk).unwrap();
+    /// }
+    ///
+    /// let mut blob = db.open_blob("Attachment", "data", id).unwrap();
+    /// let mut buf = vec![0; chunk.len()];
+    /// let mut total = 0;
+    /// while total < SIZE {
+    ///     blob.read_exact(&mut buf).unwrap();
+    ///     assert_eq!(buf, chunk);
+    ///     total += buf.len();
+    /// }
+    /// assert_eq!(blob.read(&mut buf).unwrap(), 0);
+    /// ```
+    pub fn open_blob(&self, table: &str, column: &str, rowid: i64) -> Result<Blob, KinglerError> {
+        Self::validate_identifier(table)?;
+        Self::validate_identifier(column)?;
+
+        let sqlite = self.connect()?;
+        let len = sqlite.blob_open(table, column, rowid, true)?.len();
+        Ok(Blob {
+            sqlite,
+            table_name: table.to_string(),
+            column: column.to_string(),
+            rowid,
+            len,
+            position: 0,
+        })
+    }
+}
@@ -0,0 +1,426 @@
+use crate::{Kingler, KinglerError, TableNaming, DEFAULT_INSERT_CHUNK_SIZE};
+
+/// Configures a [`Kingler`] handle before creating it
+///
+/// Created with [`Kingler::builder`]. Options left unset keep the defaults of
+/// [`Kingler::new`].
+///
+/// # Example
+/// ```rust
+/// # use kingler::{Kingler, Table};
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize, Table)]
+/// struct Client {
+///     id: Option<i64>,
+///     name: String,
+/// }
+///
+/// # let path = std::env::temp_dir().join("kingler_builder_table_prefix.db");
+/// # let _ = std::fs::remove_file(&path);
+/// let db = Kingler::builder("sqlite".to_string(), path.to_string_lossy().to_string())
+///     .table_prefix("tenant42_")
+///     .build()
+///     .unwrap();
+/// db.create_table(Client { id: None, name: String::new() }).unwrap();
+/// db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+/// assert_eq!(db.find_all::<Client>().unwrap().len(), 1);
+///
+/// // The records live in the prefixed table
+/// let rows = db.find_all_dynamic("tenant42_Client").unwrap();
+/// assert_eq!(rows.len(), 1);
+/// ```
+pub struct KinglerBuilder {
+    database: String,
+    uri: String,
+    read_uri: Option<String>,
+    table_prefix: String,
+    lowercase_table_names: bool,
+    empty_string_as_null: bool,
+    foreign_keys: bool,
+    cache_size_kb: Option<u32>,
+    application_id: Option<i32>,
+    user_version: Option<i32>,
+    capture_sql: bool,
+    create_dirs: bool,
+    insert_chunk_size: usize,
+    #[cfg(feature = "sqlcipher")]
+    encryption_key: Option<String>,
+}
+
+impl KinglerBuilder {
+    /// Routes reads to a separate database, see [`Kingler::with_read_write`]
+    pub fn read_uri(mut self, read_uri: String) -> Self {
+        self.read_uri = Some(read_uri);
+        self
+    }
+
+    /// Prepends a prefix to every table name derived from a struct
+    ///
+    /// The prefix applies consistently to table creation, inserts, updates,
+    /// deletes, queries and the tables created by
+    /// [`Kingler::create_relationship`], which lets several tenants share a
+    /// database (e.g. `tenant42_Client`). Methods taking a table name as a
+    /// string, such as [`Kingler::find_all_dynamic`], use it as given.
+    pub fn table_prefix(mut self, table_prefix: &str) -> Self {
+        self.table_prefix = table_prefix.to_string();
+        self
+    }
+
+    /// Folds the table names derived from structs to lowercase, prefix included
+    ///
+    /// Struct names keep their case (`Client`), while some databases fold
+    /// unquoted names and tools sharing the database may expect lowercase
+    /// names. Every operation deriving a table name from a struct applies the
+    /// same policy, so tables are created and queried under the same name.
+    /// Methods taking a table name as a string use it as given.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_builder_lowercase_table_names.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::builder("sqlite".to_string(), path.to_string_lossy().to_string())
+    ///     .lowercase_table_names(true)
+    ///     .build()
+    ///     .unwrap();
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+    /// assert_eq!(db.find_all::<Client>().unwrap().len(), 1);
+    ///
+    /// let conn = rusqlite::Connection::open(&path).unwrap();
+    /// let name: String = conn.query_row(
+    ///     "SELECT name FROM sqlite_master WHERE type = 'table' AND name LIKE 'client'",
+    ///     [],
+    ///     |row| row.get(0),
+    /// ).unwrap();
+    /// assert_eq!(name, "client");
+    /// ```
+    pub fn lowercase_table_names(mut self, lowercase_table_names: bool) -> Self {
+        self.lowercase_table_names = lowercase_table_names;
+        self
+    }
+
+    /// Writes empty strings to text columns as NULL in inserts and updates
+    ///
+    /// Suits records filled from web forms, whose untouched fields arrive as
+    /// `""`. Off by default, so empty strings are stored as given. Columns of
+    /// other types, including JSON columns, are unaffected, while tables
+    /// without column metadata apply it to every string.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Signup {
+    ///     id: Option<i64>,
+    ///     name: Option<String>,
+    ///     email: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_builder_empty_string_as_null.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::builder("sqlite".to_string(), path.to_string_lossy().to_string())
+    ///     .empty_string_as_null(true)
+    ///     .build()
+    ///     .unwrap();
+    /// db.create_table(Signup { id: None, name: None, email: String::new() }).unwrap();
+    ///
+    /// let form = Signup { id: None, name: Some(String::new()), email: "jane@example.com".to_string() };
+    /// let id = db.insert(&form).unwrap();
+    /// let stored = db.find_by_id::<Signup>(id).unwrap().unwrap();
+    /// assert_eq!(stored.name, None);
+    /// assert_eq!(stored.email, "jane@example.com");
+    ///
+    /// let id = stored.id.unwrap();
+    /// db.update_many::<Signup>(&[(id, vec![("name", KinglerValue::from("Jane"))])]).unwrap();
+    /// db.update_many::<Signup>(&[(id, vec![("name", KinglerValue::from(""))])]).unwrap();
+    /// assert_eq!(db.find_by_id::<Signup>(id).unwrap().unwrap().name, None);
+    /// db.update_all::<Signup>(&[("name", KinglerValue::from("Jane"))]).unwrap();
+    /// db.update_all::<Signup>(&[("name", KinglerValue::from(""))]).unwrap();
+    /// assert_eq!(db.find_by_id::<Signup>(id).unwrap().unwrap().name, None);
+    /// ```
+    pub fn empty_string_as_null(mut self, empty_string_as_null: bool) -> Self {
+        self.empty_string_as_null = empty_string_as_null;
+        self
+    }
+
+    /// Enforces foreign keys on every connection (`PRAGMA foreign_keys = ON`)
+    ///
+    /// SQLite ignores foreign keys unless each connection enables them, so
+    /// without this option records referencing missing ones can be written and
+    /// the `ON DELETE` actions of
+    /// [`Kingler::create_relationship_on_delete`] never run.
+    pub fn foreign_keys(mut self, foreign_keys: bool) -> Self {
+        self.foreign_keys = foreign_keys;
+        self
+    }
+
+    /// Sets the page cache of every connection to `cache_size_kb` KiB
+    /// (`PRAGMA cache_size`)
+    ///
+    /// SQLite reads a positive `cache_size` as a number of pages and a negative
+    /// one as a size in KiB, so the option runs `PRAGMA cache_size = -n`, which
+    /// doesn't depend on the page size. A larger cache keeps more of the
+    /// database in memory, which helps read-heavy workloads. SQLite's default
+    /// is 2000 KiB.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::Kingler;
+    /// # let path = std::env::temp_dir().join("kingler_builder_cache_size_kb.db");
+    /// let db = Kingler::builder("sqlite".to_string(), path.to_string_lossy().to_string())
+    ///     .cache_size_kb(65536)
+    ///     .build()
+    ///     .unwrap();
+    /// let cache_size: i64 = db.with_connection(|conn| {
+    ///     conn.query_row("PRAGMA cache_size", [], |row| row.get(0))
+    /// }).unwrap();
+    /// assert_eq!(cache_size, -65536);
+    /// ```
+    pub fn cache_size_kb(mut self, cache_size_kb: u32) -> Self {
+        self.cache_size_kb = Some(cache_size_kb);
+        self
+    }
+
+    /// Stamps the database file with an application id (`PRAGMA application_id`)
+    ///
+    /// The id is stored in the file header and tells which application a file
+    /// belongs to, which suits databases used as a file format. It is written
+    /// once, when [`build`](Self::build) opens the database, and read back with
+    /// [`Kingler::application_id`].
+    pub fn application_id(mut self, application_id: i32) -> Self {
+        self.application_id = Some(application_id);
+        self
+    }
+
+    /// Sets the schema version stored in the database file (`PRAGMA user_version`)
+    ///
+    /// SQLite doesn't use the number itself, so it serves as a lightweight
+    /// schema version: read it back with [`Kingler::user_version`] to tell
+    /// which migrations a file needs. It is written once, when
+    /// [`build`](Self::build) opens the database.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::Kingler;
+    /// # let path = std::env::temp_dir().join("kingler_builder_user_version.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// # let uri = path.to_string_lossy().to_string();
+    /// let db = Kingler::new("sqlite".to_string(), uri.clone());
+    /// assert_eq!(db.user_version().unwrap(), 0);
+    ///
+    /// let db = Kingler::builder("sqlite".to_string(), uri.clone())
+    ///     .application_id(0x4b494e47)
+    ///     .user_version(3)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(db.user_version().unwrap(), 3);
+    ///
+    /// // The values are stored in the file
+    /// let db = Kingler::new("sqlite".to_string(), uri);
+    /// assert_eq!(db.user_version().unwrap(), 3);
+    /// assert_eq!(db.application_id().unwrap(), 0x4b494e47);
+    /// ```
+    pub fn user_version(mut self, user_version: i32) -> Self {
+        self.user_version = Some(user_version);
+        self
+    }
+
+    /// Records the last statement run and its parameters, to be inspected with
+    /// [`Kingler::last_sql`] when debugging
+    pub fn capture_sql(mut self, capture_sql: bool) -> Self {
+        self.capture_sql = capture_sql;
+        self
+    }
+
+    /// Creates the missing parent directories of the database file before
+    /// opening it
+    ///
+    /// Off by default, so a mistyped path fails instead of creating directories.
+    ///
+    /// # Errors
+    /// Operations return `KinglerError::Io` if the directories can't be created.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    ///
+    /// # let root = std::env::temp_dir().join("kingler_builder_create_dirs");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// let path = root.join("subdir").join("nested").join("db.sqlite");
+    /// let db = Kingler::builder("sqlite".to_string(), path.to_string_lossy().to_string())
+    ///     .create_dirs(true)
+    ///     .build()
+    ///     .unwrap();
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// assert!(path.is_file());
+    /// ```
+    pub fn create_dirs(mut self, create_dirs: bool) -> Self {
+        self.create_dirs = create_dirs;
+        self
+    }
+
+    /// Sets how many records each statement of [`Kingler::insert_iter`] writes,
+    /// [`DEFAULT_INSERT_CHUNK_SIZE`] unless set
+    ///
+    /// Larger chunks mean fewer statements, but each one binds more parameters
+    /// and chunks are capped to the most parameters SQLite binds.
+    ///
+    /// # Errors
+    /// [`build`](Self::build) returns `KinglerError::InvalidArgument` for a size
+    /// of 0.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError};
+    /// let db = Kingler::builder("sqlite".to_string(), "unused.db".to_string())
+    ///     .insert_chunk_size(100)
+    ///     .build();
+    /// assert!(db.is_ok());
+    ///
+    /// let db = Kingler::builder("sqlite".to_string(), "unused.db".to_string())
+    ///     .insert_chunk_size(0)
+    ///     .build();
+    /// assert!(matches!(db, Err(KinglerError::InvalidArgument(_))));
+    /// ```
+    pub fn insert_chunk_size(mut self, insert_chunk_size: usize) -> Self {
+        self.insert_chunk_size = insert_chunk_size;
+        self
+    }
+
+    /// Encrypts the database with SQLCipher, unlocking it with the given key
+    ///
+    /// A new database is encrypted with the key, an existing one must have been
+    /// encrypted with it. Requires the `sqlcipher` feature.
+    ///
+    /// # Errors
+    /// Every operation returns `KinglerError::InvalidEncryptionKey` if the key
+    /// doesn't decrypt an existing database.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Secret {
+    ///     id: Option<i64>,
+    ///     value: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_builder_encryption_key.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// # let uri = path.to_string_lossy().to_string();
+    /// let db = Kingler::builder("sqlite".to_string(), uri.clone())
+    ///     .encryption_key("correct horse")
+    ///     .build()
+    ///     .unwrap();
+    /// db.create_table(Secret { id: None, value: String::new() }).unwrap();
+    /// db.insert(&Secret { id: None, value: "hunter2".to_string() }).unwrap();
+    /// drop(db);
+    ///
+    /// let wrong = Kingler::builder("sqlite".to_string(), uri.clone())
+    ///     .encryption_key("battery staple")
+    ///     .build()
+    ///     .unwrap();
+    /// let result = wrong.find_all::<Secret>();
+    /// assert!(matches!(result, Err(KinglerError::InvalidEncryptionKey)));
+    ///
+    /// let db = Kingler::builder("sqlite".to_string(), uri)
+    ///     .encryption_key("correct horse")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(db.find_all::<Secret>().unwrap()[0].value, "hunter2");
+    /// ```
+    #[cfg(feature = "sqlcipher")]
+    pub fn encryption_key(mut self, key: &str) -> Self {
+        self.encryption_key = Some(key.to_string());
+        self
+    }
+
+    /// Creates the configured handle
+    ///
+    /// # Errors
+    /// Returns `KinglerError::InvalidIdentifier` if the table prefix isn't made
+    /// of letters, digits and underscores starting with a letter or underscore,
+    /// and `KinglerError::InvalidArgument` if the insert chunk size is 0.
+    /// When an application id or user version is set, the database is opened
+    /// to write it, so errors opening it are returned as well.
+    pub fn build(self) -> Result<Kingler, KinglerError> {
+        if !self.table_prefix.is_empty() {
+            Kingler::validate_identifier(&self.table_prefix)?;
+        }
+        if self.insert_chunk_size == 0 {
+            return Err(KinglerError::InvalidArgument("insert chunk size must be at least 1".to_string()));
+        }
+        let pragmas: Vec<String> = [("application_id", self.application_id), ("user_version", self.user_version)]
+            .into_iter()
+            .filter_map(|(pragma, value)| value.map(|value| format!("PRAGMA {} = {};", pragma, value)))
+            .collect();
+        let kingler = Kingler {
+            database: self.database,
+            uri: self.uri,
+            read_uri: self.read_uri,
+            naming: TableNaming {
+                prefix: self.table_prefix,
+                lowercase: self.lowercase_table_names,
+            },
+            sql_log: self.capture_sql.then(Default::default),
+            create_dirs: self.create_dirs,
+            insert_chunk_size: self.insert_chunk_size,
+            hooks: Default::default(),
+            functions: Default::default(),
+            empty_string_as_null: self.empty_string_as_null,
+            foreign_keys: self.foreign_keys,
+            cache_size_kb: self.cache_size_kb,
+            #[cfg(feature = "sqlcipher")]
+            encryption_key: self.encryption_key,
+        };
+        if !pragmas.is_empty() {
+            let sqlite = kingler.connect()?;
+            sqlite.connection().execute_batch(&pragmas.concat())?;
+        }
+        Ok(kingler)
+    }
+}
+
+impl Kingler {
+    /// Starts configuring a new instance of the Kingler ORM
+    ///
+    /// # Arguments
+    /// * `database` - The type of database ("sqlite" or "mysql")
+    /// * `uri` - The connection string or file path, used for writes and, unless
+    ///   [`KinglerBuilder::read_uri`] is set, reads
+    pub fn builder(database: String, uri: String) -> KinglerBuilder {
+        KinglerBuilder {
+            database,
+            uri,
+            read_uri: None,
+            table_prefix: String::new(),
+            lowercase_table_names: false,
+            empty_string_as_null: false,
+            foreign_keys: false,
+            cache_size_kb: None,
+            application_id: None,
+            user_version: None,
+            capture_sql: false,
+            create_dirs: false,
+            insert_chunk_size: DEFAULT_INSERT_CHUNK_SIZE,
+            #[cfg(feature = "sqlcipher")]
+            encryption_key: None,
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use crate::{Database, Dialect, KinglerValue, SqlType};
+
+/// Digits of an encoded 128-bit integer, enough for `u128::MAX`
+const BIG_INTEGER_DIGITS: usize = 39;
+
+/// Describes a single table column
+///
+/// Column metadata is usually generated by `#[derive(Table)]`, but it can
+/// also be built by hand when implementing `Table` manually.
+///
+/// # Example
+/// ```rust
+/// use kingler::{Column, SqlType};
+///
+/// let column = Column::new("email", SqlType::Text).unique().collate("NOCASE");
+/// assert_eq!(column.definition(), "TEXT UNIQUE COLLATE NOCASE");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Column {
+    /// Name of the column
+    pub name: &'static str,
+    /// Type of the column, rendered by each backend's dialect
+    pub sql_type: SqlType,
+    /// Whether the column is the table's primary key
+    pub primary_key: bool,
+    /// Whether an INTEGER primary key uses `AUTOINCREMENT`
+    pub autoincrement: bool,
+    /// Whether the column has a UNIQUE constraint
+    pub unique: bool,
+    /// Collation sequence used to compare values (e.g. "NOCASE")
+    pub collate: Option<&'static str>,
+    /// For enums stored as TEXT, checks that a stored value names a known variant
+    pub enum_variant: Option<fn(&serde_json::Value) -> bool>,
+    /// Maximum length of a TEXT column, rendered as VARCHAR where supported
+    pub max_length: Option<u32>,
+    /// Whether the column stores a value serialized as JSON text
+    pub json: bool,
+    /// Whether the column stores a 128-bit integer as fixed-width TEXT
+    pub big_integer: bool,
+    /// Expression computing a generated column's value, which is never written
+    pub generated: Option<&'static str>,
+    /// Whether a generated column is stored instead of computed when read
+    pub stored: bool,
+    /// Description of the column, kept with the schema
+    pub comment: Option<&'static str>,
+}
+
+impl Column {
+    /// Creates a plain column with the given name and SQL type
+    pub fn new(name: &'static str, sql_type: SqlType) -> Self {
+        Column {
+            name,
+            sql_type,
+            primary_key: false,
+            autoincrement: true,
+            unique: false,
+            collate: None,
+            enum_variant: None,
+            max_length: None,
+            json: false,
+            big_integer: false,
+            generated: None,
+            stored: false,
+            comment: None,
+        }
+    }
+
+    /// Marks the column as the primary key
+    /// 
+    /// INTEGER primary keys are auto incremented.
+    pub fn primary_key(mut self) -> Self {
+        self.primary_key = true;
+        self
+    }
+
+    /// Sets whether an INTEGER primary key uses `AUTOINCREMENT` (the default)
+    /// 
+    /// Without `AUTOINCREMENT` the key is a plain alias of SQLite's rowid, which
+    /// avoids the bookkeeping in `sqlite_sequence` but lets the ids of deleted
+    /// records at the end of the table be reused. With it ids only ever grow.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use kingler::Table;
+    /// 
+    /// #[derive(Table)]
+    /// struct Tag {
+    ///     #[column(autoincrement = false)]
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// assert_eq!(Tag::columns()[0].definition(), "INTEGER PRIMARY KEY");
+    /// ```
+    pub fn autoincrement(mut self, autoincrement: bool) -> Self {
+        self.autoincrement = autoincrement;
+        self
+    }
+
+    /// Adds a UNIQUE constraint to the column
+    pub fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    /// Sets the collation sequence of the column
+    pub fn collate(mut self, collate: &'static str) -> Self {
+        self.collate = Some(collate);
+        self
+    }
+
+    /// Stores an enum as the TEXT name of its variant
+    /// 
+    /// `is_variant` tells whether a stored value is a known variant of the enum.
+    pub fn enum_text(mut self, is_variant: fn(&serde_json::Value) -> bool) -> Self {
+        self.enum_variant = Some(is_variant);
+        self
+    }
+
+    /// Limits the length of a TEXT column
+    /// 
+    /// Backends with a length-limited text type render it as `VARCHAR(n)`.
+    /// SQLite treats every text the same, so the column stays TEXT there.
+    pub fn max_length(mut self, max_length: u32) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Stores the value serialized as JSON text
+    /// 
+    /// Nested structs, maps and lists are written as a JSON document and parsed
+    /// back when read, so the field can be any type implementing serde's traits.
+    /// SQLite's JSON1 functions can then query inside the document, see
+    /// [`Query::filter_json_eq`](crate::Query::filter_json_eq).
+    pub fn json(mut self) -> Self {
+        self.json = true;
+        self
+    }
+
+    /// Stores a 128-bit integer as fixed-width TEXT
+    /// 
+    /// SQLite integers are 64-bit, so `i128` and `u128` values are written as
+    /// text to keep every digit and parsed back when read. The text is a sign
+    /// character followed by 39 digits, enough for `u128::MAX`: `0` and the
+    /// zero-padded number for positive values, `-` and the nines' complement of
+    /// the number for negative ones. Comparing two such texts then gives the
+    /// same result as comparing their numbers, so SQL ordering and comparisons
+    /// work as on an INTEGER column. The query builder, the filters of
+    /// [`find_where`](crate::Kingler::find_where) and the bulk updates encode
+    /// the values written to or compared with such a column; raw SQL has to
+    /// compare with encoded text. `#[derive(Table)]` sets this for `i128` and `u128`
+    /// fields.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Account {
+    ///     id: Option<i64>,
+    ///     balance: i128,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_column_big_integer.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Account { id: None, balance: 0 }).unwrap();
+    /// 
+    /// let balance = i64::MAX as i128 * 1000 + 7;
+    /// let id = db.insert(&Account { id: None, balance }).unwrap();
+    /// assert_eq!(db.find_by_id::<Account>(id).unwrap().unwrap().balance, balance);
+    /// 
+    /// let id = db.insert(&Account { id: None, balance: i128::MIN }).unwrap();
+    /// assert_eq!(db.find_by_id::<Account>(id).unwrap().unwrap().balance, i128::MIN);
+    /// 
+    /// for balance in [100, 9, -10, 10, -9, 0, i128::MAX] {
+    ///     db.insert(&Account { id: None, balance }).unwrap();
+    /// }
+    /// let balances: Vec<i128> = Query::<Account>::new()
+    ///     .order_by("balance")
+    ///     .fetch(&db)
+    ///     .unwrap()
+    ///     .into_iter()
+    ///     .map(|account| account.balance)
+    ///     .collect();
+    /// assert_eq!(balances, vec![i128::MIN, -10, -9, 0, 9, 10, 100, balance, i128::MAX]);
+    /// 
+    /// let nines = Query::<Account>::new().filter("balance", 9).fetch(&db).unwrap();
+    /// assert_eq!(nines.len(), 1);
+    /// let large = Query::<Account>::new()
+    ///     .filter_in("balance", vec![balance.to_string(), i128::MAX.to_string()])
+    ///     .count(&db)
+    ///     .unwrap();
+    /// assert_eq!(large, 2);
+    /// 
+    /// db.update_many::<Account>(&[(1, vec![("balance", KinglerValue::from("-1"))])]).unwrap();
+    /// let lowest = Query::<Account>::new().order_by("balance").first(&db).unwrap().unwrap();
+    /// assert_eq!((lowest.id, lowest.balance), (Some(2), i128::MIN));
+    /// let second = Query::<Account>::new().filter("balance", -1).first(&db).unwrap().unwrap();
+    /// assert_eq!(second.id, Some(1));
+    /// 
+    /// let updated = db.update_where::<Account>(&[("balance", KinglerValue::from(5))], &[("balance", (-1).into())]).unwrap();
+    /// assert_eq!(updated, 1);
+    /// let fives = db.find_where::<Account>(&[("balance", 5.into())]).unwrap();
+    /// assert_eq!(fives[0].id, Some(1));
+    /// ```
+    pub fn big_integer(mut self) -> Self {
+        self.big_integer = true;
+        self
+    }
+
+    /// Converts a value written to or compared with the column into the value
+    /// stored for it, encoding the integers of a big-integer column
+    pub(crate) fn stored_value(&self, value: KinglerValue) -> KinglerValue {
+        let encoded = match &value {
+            KinglerValue::Int(number) if self.big_integer => Self::encode_big_integer(&number.to_string()),
+            KinglerValue::Text(number) if self.big_integer => Self::encode_big_integer(number),
+            _ => None,
+        };
+        encoded.map(KinglerValue::Text).unwrap_or(value)
+    }
+
+    /// Encodes the decimal text of an integer as stored in a big-integer
+    /// column, see [`Column::big_integer`]
+    /// 
+    /// Returns `None` for text that is not an integer of at most 39 digits.
+    pub(crate) fn encode_big_integer(number: &str) -> Option<String> {
+        let (negative, digits) = match number.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, number),
+        };
+        if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        let digits = digits.trim_start_matches('0');
+        if digits.len() > BIG_INTEGER_DIGITS {
+            return None;
+        }
+        let padded = format!("{:0>width$}", digits, width = BIG_INTEGER_DIGITS);
+        Some(if negative && !digits.is_empty() {
+            format!("-{}", nines_complement(&padded))
+        } else {
+            format!("0{}", padded)
+        })
+    }
+
+    /// Decodes the text stored in a big-integer column back into the decimal
+    /// text of its integer
+    /// 
+    /// Returns `None` for text that is not encoded, e.g. written before the
+    /// encoding was used.
+    pub(crate) fn decode_big_integer(text: &str) -> Option<String> {
+        if text.len() != BIG_INTEGER_DIGITS + 1 || !text[1..].bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        let (negative, digits) = match text.as_bytes()[0] {
+            b'-' => (true, nines_complement(&text[1..])),
+            b'0' => (false, text[1..].to_string()),
+            _ => return None,
+        };
+        let digits = digits.trim_start_matches('0');
+        Some(match (negative, digits.is_empty()) {
+            (_, true) => "0".to_string(),
+            (true, false) => format!("-{}", digits),
+            (false, false) => digits.to_string(),
+        })
+    }
+
+    /// Computes the column from other columns of the record
+    /// 
+    /// Renders `GENERATED ALWAYS AS (expression)`, `STORED` when `stored` is set
+    /// and `VIRTUAL` (computed when read) otherwise. Inserts and updates skip the
+    /// column, the database fills it in.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct LineItem {
+    ///     id: Option<i64>,
+    ///     price: f64,
+    ///     quantity: i64,
+    ///     #[column(generated = "price * quantity", stored)]
+    ///     total: f64,
+    /// }
+    /// 
+    /// assert_eq!(
+    ///     LineItem::columns()[3].definition(),
+    ///     "REAL GENERATED ALWAYS AS (price * quantity) STORED"
+    /// );
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_column_generated.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(LineItem { id: None, price: 0.0, quantity: 0, total: 0.0 }).unwrap();
+    /// let id = db.insert(&LineItem { id: None, price: 2.5, quantity: 4, total: 0.0 }).unwrap();
+    /// assert_eq!(db.find_by_id::<LineItem>(id).unwrap().unwrap().total, 10.0);
+    /// ```
+    pub fn generated(mut self, expression: &'static str, stored: bool) -> Self {
+        self.generated = Some(expression);
+        self.stored = stored;
+        self
+    }
+
+    /// Describes the column
+    /// 
+    /// MySQL renders it as the column's native `COMMENT` and PostgreSQL sets it
+    /// with [`build_comment_on_column`](crate::sql::build_comment_on_column).
+    /// SQLite has no column
+    /// comments, so [`Kingler::create_table`](crate::Kingler::create_table)
+    /// stores it in the `_kingler_column_meta` table instead, where
+    /// [`Kingler::describe_table`](crate::Kingler::describe_table) reads it back.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use kingler::{Column, Database, SqlType};
+    /// 
+    /// let column = Column::new("price", SqlType::Real).comment("Unit price in cents");
+    /// assert_eq!(column.definition_for(Database::Mysql), "DOUBLE COMMENT 'Unit price in cents'");
+    /// assert_eq!(column.definition_for(Database::Sqlite), "REAL");
+    /// ```
+    pub fn comment(mut self, comment: &'static str) -> Self {
+        self.comment = Some(comment);
+        self
+    }
+
+    /// Renders the SQLite column definition without its name
+    /// (e.g. "TEXT UNIQUE COLLATE NOCASE")
+    pub fn definition(&self) -> String {
+        self.definition_for(Database::Sqlite)
+    }
+
+    /// Renders the column definition for the given backend without its name
+    /// 
+    /// # Example
+    /// ```rust
+    /// use kingler::{Column, Database, SqlType};
+    /// 
+    /// let column = Column::new("username", SqlType::Text).max_length(255);
+    /// assert_eq!(column.definition_for(Database::Mysql), "VARCHAR(255)");
+    /// assert_eq!(column.definition_for(Database::Sqlite), "TEXT");
+    /// ```
+    /// 
+    /// The length can also be set with `#[column(max_length = ...)]`:
+    /// ```rust
+    /// use kingler::{Database, Table};
+    /// 
+    /// #[derive(Table)]
+    /// struct Profile {
+    ///     id: Option<i64>,
+    ///     #[column(max_length = 255)]
+    ///     username: String,
+    /// }
+    /// 
+    /// let username = &Profile::columns()[1];
+    /// assert_eq!(username.definition_for(Database::Mysql), "VARCHAR(255)");
+    /// assert_eq!(username.definition_for(Database::Sqlite), "TEXT");
+    /// ```
+    pub fn definition_for(&self, database: Database) -> String {
+        if self.primary_key && self.autoincrement && self.sql_type == SqlType::Integer {
+            return database.id_column().to_string();
+        }
+
+        let mut definition = match (database, self.max_length) {
+            (Database::Mysql | Database::Postgres, Some(max_length)) if self.sql_type == SqlType::Text => {
+                format!("VARCHAR({})", max_length)
+            }
+            _ => database.render(self.sql_type),
+        };
+        if let Some(expression) = self.generated {
+            let storage = if self.stored { "STORED" } else { "VIRTUAL" };
+            definition.push_str(&format!(" GENERATED ALWAYS AS ({}) {}", expression, storage));
+        }
+        if self.primary_key {
+            definition.push_str(" PRIMARY KEY");
+        }
+        if self.unique {
+            definition.push_str(" UNIQUE");
+        }
+        let collate = match (database, self.collate) {
+            (Database::Sqlite, collate) => collate,
+            (Database::Mysql, Some("NOCASE")) => Some("utf8mb4_general_ci"),
+            (Database::Mysql, Some("BINARY")) => Some("utf8mb4_bin"),
+            (Database::Mysql, _) => None,
+            (Database::Postgres, _) => None,
+        };
+        if let Some(collate) = collate {
+            definition.push_str(" COLLATE ");
+            definition.push_str(collate);
+        }
+        if let (Database::Mysql, Some(comment)) = (database, self.comment) {
+            definition.push_str(&format!(" COMMENT '{}'", comment.replace('\'', "''")));
+        }
+        definition
+    }
+}
+
+/// Replaces every ASCII digit with its difference to 9
+fn nines_complement(digits: &str) -> String {
+    digits.bytes().map(|byte| char::from(b'9' - byte + b'0')).collect()
+}
@@ -0,0 +1,23 @@
+/// Database backends the ORM generates SQL for
+///
+/// How each backend spells SQL is described by its [`Dialect`](crate::Dialect)
+/// implementation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Database {
+    Sqlite,
+    Mysql,
+    Postgres,
+}
+
+impl Database {
+    /// Looks up a backend by the name given to `Kingler::new` ("sqlite", "mysql"
+    /// or "postgres")
+    pub fn from_name(name: &str) -> Option<Database> {
+        match name {
+            "sqlite" => Some(Database::Sqlite),
+            "mysql" => Some(Database::Mysql),
+            "postgres" => Some(Database::Postgres),
+            _ => None,
+        }
+    }
+}
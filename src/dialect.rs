@@ -0,0 +1,124 @@
+use crate::{Database, SqlType};
+
+/// How a database backend spells the SQL the ORM generates
+///
+/// Schema generation and the statement builders ask the dialect for types,
+/// identifier quoting and placeholders instead of hardcoding SQLite's, so each
+/// backend only has to describe its differences.
+///
+/// # Example
+/// ```rust
+/// use kingler::{Database, Dialect};
+///
+/// assert_eq!(Database::Sqlite.id_column(), "INTEGER PRIMARY KEY AUTOINCREMENT");
+/// assert_eq!(Database::Sqlite.int_type(), "INTEGER");
+/// assert_eq!(Database::Sqlite.text_type(), "TEXT");
+/// assert_eq!(Database::Sqlite.quote_ident("col"), "\"col\"");
+/// assert_eq!(Database::Sqlite.placeholder(2), "?");
+///
+/// assert_eq!(Database::Mysql.id_column(), "INTEGER PRIMARY KEY AUTO_INCREMENT");
+/// assert_eq!(Database::Mysql.int_type(), "BIGINT");
+/// assert_eq!(Database::Mysql.quote_ident("col"), "`col`");
+/// assert_eq!(Database::Mysql.placeholder(2), "?");
+///
+/// assert_eq!(Database::Postgres.id_column(), "BIGSERIAL PRIMARY KEY");
+/// assert_eq!(Database::Postgres.real_type(), "DOUBLE PRECISION");
+/// assert_eq!(Database::Postgres.quote_ident("col"), "\"col\"");
+/// assert_eq!(Database::Postgres.placeholder(2), "$2");
+/// ```
+pub trait Dialect {
+    /// Definition of an auto-assigned integer primary key
+    fn id_column(&self) -> &'static str;
+    /// Type of text columns
+    fn text_type(&self) -> &'static str;
+    /// Type of 64-bit integer columns
+    fn int_type(&self) -> &'static str;
+    /// Type of floating point columns
+    fn real_type(&self) -> &'static str;
+    /// Type of boolean columns
+    fn bool_type(&self) -> &'static str;
+    /// Type of binary columns
+    fn blob_type(&self) -> &'static str;
+    /// Type of date and time columns
+    fn datetime_type(&self) -> &'static str;
+    /// Quotes a table or column name for use in SQL, doubling the quote
+    /// characters inside it
+    fn quote_ident(&self, ident: &str) -> String;
+    /// Placeholder of the parameter at the given position, counting from 1
+    fn placeholder(&self, index: usize) -> String;
+
+    /// Renders the type of column metadata in the dialect's DDL
+    fn render(&self, sql_type: SqlType) -> String {
+        match sql_type {
+            SqlType::Integer => self.int_type(),
+            SqlType::Real => self.real_type(),
+            SqlType::Text => self.text_type(),
+            SqlType::Boolean => self.bool_type(),
+            SqlType::Blob => self.blob_type(),
+            SqlType::Datetime => self.datetime_type(),
+        }
+        .to_string()
+    }
+}
+
+impl Dialect for Database {
+    fn id_column(&self) -> &'static str {
+        match self {
+            Database::Sqlite => "INTEGER PRIMARY KEY AUTOINCREMENT",
+            Database::Mysql => "INTEGER PRIMARY KEY AUTO_INCREMENT",
+            Database::Postgres => "BIGSERIAL PRIMARY KEY",
+        }
+    }
+
+    fn text_type(&self) -> &'static str {
+        "TEXT"
+    }
+
+    fn int_type(&self) -> &'static str {
+        match self {
+            Database::Sqlite => "INTEGER",
+            Database::Mysql | Database::Postgres => "BIGINT",
+        }
+    }
+
+    fn real_type(&self) -> &'static str {
+        match self {
+            Database::Sqlite => "REAL",
+            Database::Mysql => "DOUBLE",
+            Database::Postgres => "DOUBLE PRECISION",
+        }
+    }
+
+    fn bool_type(&self) -> &'static str {
+        "BOOLEAN"
+    }
+
+    fn blob_type(&self) -> &'static str {
+        match self {
+            Database::Sqlite | Database::Mysql => "BLOB",
+            Database::Postgres => "BYTEA",
+        }
+    }
+
+    fn datetime_type(&self) -> &'static str {
+        match self {
+            Database::Sqlite => "TEXT",
+            Database::Mysql => "DATETIME",
+            Database::Postgres => "TIMESTAMP",
+        }
+    }
+
+    fn quote_ident(&self, ident: &str) -> String {
+        match self {
+            Database::Sqlite | Database::Postgres => format!("\"{}\"", ident.replace('"', "\"\"")),
+            Database::Mysql => format!("`{}`", ident.replace('`', "``")),
+        }
+    }
+
+    fn placeholder(&self, index: usize) -> String {
+        match self {
+            Database::Sqlite | Database::Mysql => "?".to_string(),
+            Database::Postgres => format!("${}", index),
+        }
+    }
+}
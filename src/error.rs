@@ -0,0 +1,123 @@
+use std::fmt;
+
+/// Errors returned by the Kingler ORM
+#[derive(Debug)]
+pub enum KinglerError {
+    /// An error reported by the SQLite driver
+    Sqlite(rusqlite::Error),
+    /// A record could not be converted to or from its stored representation
+    Serialization(serde_json::Error),
+    /// The configured database type is not supported
+    UnsupportedDatabase(String),
+    /// A table or column name is not a valid SQL identifier
+    InvalidIdentifier(String),
+    /// A column name does not belong to the table
+    UnknownColumn(String),
+    /// A stored value is not a known variant of the column's enum
+    InvalidEnumVariant { column: String, value: String },
+    /// A record of the given table doesn't serialize to named columns, or the
+    /// table has no column metadata to plan its schema from
+    NoColumns(String),
+    /// A record of the given table has no primary key value
+    MissingPrimaryKey(String),
+    /// The given table doesn't exist in the database
+    TableNotFound(String),
+    /// A table with the given name already exists
+    TableExists(String),
+    /// An operation on the given table would affect every record because no
+    /// filter was given
+    MissingFilter(String),
+    /// The encryption key doesn't decrypt the database (`sqlcipher` feature)
+    InvalidEncryptionKey,
+    /// A filesystem operation failed, e.g. creating the database directory
+    Io(std::io::Error),
+    /// An argument or option is out of its accepted range, e.g. a batch size
+    /// of 0
+    InvalidArgument(String),
+    /// A record was rejected by its `Validate` implementation
+    Validation(String),
+    /// A number written to an INTEGER column doesn't fit SQLite's 64-bit
+    /// integers, such as a `u64` above `i64::MAX`, so it can't be stored exactly
+    IntegerOutOfRange { column: String, value: String },
+    /// The columns of a table don't match its struct: `missing` lists the
+    /// fields without a column, `extra` the columns without a field
+    SchemaMismatch { missing: Vec<String>, extra: Vec<String> },
+}
+
+impl fmt::Display for KinglerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KinglerError::Sqlite(err) => write!(f, "SQLite error: {}", err),
+            KinglerError::Serialization(err) => write!(f, "Serialization error: {}", err),
+            KinglerError::UnsupportedDatabase(database) => {
+                write!(f, "Database {} not supported", database)
+            }
+            KinglerError::InvalidIdentifier(name) => write!(f, "Invalid identifier: {}", name),
+            KinglerError::UnknownColumn(name) => write!(f, "Unknown column: {}", name),
+            KinglerError::InvalidEnumVariant { column, value } => {
+                write!(f, "Invalid enum variant {} in column {}", value, column)
+            }
+            KinglerError::NoColumns(table) => {
+                write!(f, "Table {} has no named columns", table)
+            }
+            KinglerError::MissingPrimaryKey(table) => {
+                write!(f, "Record for table {} has no primary key value", table)
+            }
+            KinglerError::TableNotFound(table) => write!(f, "Table {} not found", table),
+            KinglerError::TableExists(table) => write!(f, "Table {} already exists", table),
+            KinglerError::MissingFilter(table) => {
+                write!(f, "Refusing to change every record of table {} without a filter", table)
+            }
+            KinglerError::InvalidEncryptionKey => {
+                write!(f, "The encryption key doesn't decrypt the database")
+            }
+            KinglerError::Io(err) => write!(f, "IO error: {}", err),
+            KinglerError::InvalidArgument(message) => write!(f, "Invalid argument: {}", message),
+            KinglerError::Validation(message) => write!(f, "Validation failed: {}", message),
+            KinglerError::IntegerOutOfRange { column, value } => {
+                write!(f, "Integer {} is out of range for column {}", value, column)
+            }
+            KinglerError::SchemaMismatch { missing, extra } => write!(
+                f,
+                "Schema mismatch: missing columns [{}], extra columns [{}]",
+                missing.join(", "),
+                extra.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KinglerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KinglerError::Sqlite(err) => Some(err),
+            KinglerError::Serialization(err) => Some(err),
+            KinglerError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for KinglerError {
+    /// Wraps a driver error, classifying the ones with a dedicated variant
+    fn from(err: rusqlite::Error) -> Self {
+        if let rusqlite::Error::SqliteFailure(_, Some(message)) = &err {
+            if let Some(table) = message.strip_prefix("no such table: ") {
+                return KinglerError::TableNotFound(table.to_string());
+            }
+        }
+        KinglerError::Sqlite(err)
+    }
+}
+
+impl From<serde_json::Error> for KinglerError {
+    fn from(err: serde_json::Error) -> Self {
+        KinglerError::Serialization(err)
+    }
+}
+
+impl From<std::io::Error> for KinglerError {
+    fn from(err: std::io::Error) -> Self {
+        KinglerError::Io(err)
+    }
+}
@@ -0,0 +1,103 @@
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use rusqlite::functions::FunctionFlags;
+
+use crate::{Kingler, KinglerError, KinglerValue};
+
+/// Rust closure called by SQL as a scalar function, receiving the values of its
+/// arguments and returning its result
+pub type ScalarFunction = Arc<dyn Fn(&[KinglerValue]) -> Result<KinglerValue, KinglerError> + Send + Sync>;
+
+/// Scalar functions registered on a [`Kingler`] handle, added to every
+/// connection it opens
+#[derive(Default)]
+pub(crate) struct Functions {
+    /// Behind a lock so a shared handle can register more
+    functions: RwLock<Vec<(String, i32, ScalarFunction)>>,
+}
+
+impl Functions {
+    /// Adds the functions to a connection, in the order they were registered
+    pub(crate) fn register(&self, conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
+        let functions = self.functions.read().unwrap_or_else(PoisonError::into_inner);
+        for (name, arity, function) in functions.iter() {
+            // A panicking closure aborts the statement, it can't leave the
+            // connection half updated
+            let function = AssertUnwindSafe(function.clone());
+            conn.create_scalar_function(name.as_str(), *arity, FunctionFlags::SQLITE_UTF8, move |ctx| {
+                let AssertUnwindSafe(function) = &function;
+                let args: Vec<KinglerValue> = (0..ctx.len())
+                    .map(|index| KinglerValue::from(ctx.get_raw(index)))
+                    .collect();
+                function(&args).map_err(|err| rusqlite::Error::UserFunctionError(Box::new(err)))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl Kingler {
+    /// Registers a Rust closure as an SQL scalar function
+    ///
+    /// Connections are opened per operation, so the function is added to every
+    /// connection the handle opens from now on, including those of its
+    /// transactions: any query can call it, such as [`Query::where_raw`](crate::Query::where_raw)
+    /// conditions. `arity` is the number of arguments it takes, or `-1` for any
+    /// number. An error returned by the closure fails the statement calling it.
+    /// It only needs `&self`, so a handle shared between threads, e.g. behind
+    /// an `Arc`, can register functions after it is built.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_register_function.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.register_function("reverse", 1, |args| match &args[0] {
+    ///     KinglerValue::Text(text) => Ok(KinglerValue::Text(text.chars().rev().collect())),
+    ///     other => Ok(other.clone()),
+    /// }).unwrap();
+    ///
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// for name in ["anna", "otto", "john"] {
+    ///     db.insert(&Client { id: None, name: name.to_string() }).unwrap();
+    /// }
+    ///
+    /// let palindromes = Query::<Client>::new()
+    ///     .where_raw("reverse(name) = name", Vec::new())
+    ///     .fetch(&db)
+    ///     .unwrap();
+    /// assert_eq!(palindromes.len(), 2);
+    ///
+    /// let reversed = Query::<Client>::new()
+    ///     .where_raw("reverse(name) = ?", vec![KinglerValue::from("nhoj")])
+    ///     .fetch(&db)
+    ///     .unwrap();
+    /// assert_eq!(reversed[0].name, "john");
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `KinglerError::InvalidIdentifier` if the name isn't a valid
+    /// identifier.
+    pub fn register_function(
+        &self,
+        name: &str,
+        arity: i32,
+        function: impl Fn(&[KinglerValue]) -> Result<KinglerValue, KinglerError> + Send + Sync + 'static,
+    ) -> Result<(), KinglerError> {
+        Self::validate_identifier(name)?;
+        self.functions.functions
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((name.to_string(), arity, Arc::new(function)));
+        Ok(())
+    }
+}
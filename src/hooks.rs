@@ -0,0 +1,256 @@
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::Kingler;
+
+/// Callback run around a write, receiving the (prefixed) table name and the
+/// record serialized as JSON
+pub type Hook = Arc<dyn Fn(&str, &serde_json::Value) + Send + Sync>;
+
+/// Kind of change reported to the callbacks of [`Kingler::on_change`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// A row was inserted, including by an upsert adding it
+    Insert,
+    /// A row was updated
+    Update,
+    /// A row was deleted
+    Delete,
+}
+
+/// Callback run for every row changed, receiving the kind of change, the table
+/// name and the rowid of the row
+pub type ChangeHook = Arc<dyn Fn(Action, &str, i64) + Send + Sync>;
+
+/// Callbacks of one kind, behind a lock so a shared handle can register more
+pub(crate) type HookList<H> = RwLock<Vec<H>>;
+
+/// Callbacks registered on a [`Kingler`] handle, shared with its transactions
+#[derive(Default)]
+pub(crate) struct Hooks {
+    pub(crate) before_insert: HookList<Hook>,
+    pub(crate) after_insert: HookList<Hook>,
+    pub(crate) before_update: HookList<Hook>,
+    pub(crate) after_update: HookList<Hook>,
+    pub(crate) before_delete: HookList<Hook>,
+    pub(crate) after_delete: HookList<Hook>,
+    pub(crate) change: HookList<ChangeHook>,
+}
+
+impl Hooks {
+    /// Runs the callbacks in the order they were registered, only building the
+    /// value passed to them when there is one
+    ///
+    /// The callbacks run after the lock is released, so they can register
+    /// further callbacks.
+    pub(crate) fn fire(hooks: &HookList<Hook>, table_name: &str, value: impl FnOnce() -> serde_json::Value) {
+        let hooks = Self::snapshot(hooks);
+        if hooks.is_empty() {
+            return;
+        }
+        let value = value();
+        for hook in &hooks {
+            hook(table_name, &value);
+        }
+    }
+
+    /// Whether any callback of the kind is registered
+    pub(crate) fn any<H>(hooks: &HookList<H>) -> bool {
+        !hooks.read().unwrap_or_else(PoisonError::into_inner).is_empty()
+    }
+
+    /// Copies the callbacks of a kind out of their lock
+    fn snapshot<H: Clone>(hooks: &HookList<H>) -> Vec<H> {
+        hooks.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Adds a callback of a kind
+    fn push<H>(hooks: &HookList<H>, hook: H) {
+        hooks.write().unwrap_or_else(PoisonError::into_inner).push(hook);
+    }
+
+    /// Installs the change callbacks on a connection as its SQLite update hook
+    ///
+    /// The connection keeps the callbacks registered when it was opened.
+    pub(crate) fn register(&self, conn: &rusqlite::Connection) {
+        let hooks = Self::snapshot(&self.change);
+        if hooks.is_empty() {
+            return;
+        }
+        conn.update_hook(Some(move |action, _: &str, table: &str, rowid| {
+            let action = match action {
+                rusqlite::hooks::Action::SQLITE_INSERT => Action::Insert,
+                rusqlite::hooks::Action::SQLITE_UPDATE => Action::Update,
+                rusqlite::hooks::Action::SQLITE_DELETE => Action::Delete,
+                _ => return,
+            };
+            for hook in &hooks {
+                hook(action, table, rowid);
+            }
+        }));
+    }
+}
+
+impl Kingler {
+    /// Registers a callback run before each record is inserted
+    ///
+    /// Callbacks fire for the records written by [`Kingler::insert`], the batch
+    /// inserts and transactions, with the table name including its prefix and the
+    /// record as it is about to be written. They can't cancel the write; use
+    /// [`Validate`](crate::Validate) to reject records. Callbacks can be
+    /// registered through a shared `&Kingler` at any time, even from another
+    /// callback.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// # use std::sync::{Arc, Mutex};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_hooks_insert.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    ///
+    /// let audit = Arc::new(Mutex::new(Vec::new()));
+    /// let log = audit.clone();
+    /// db.on_before_insert(move |table, _| log.lock().unwrap().push(format!("before {}", table)));
+    /// let log = audit.clone();
+    /// db.on_after_insert(move |table, record| {
+    ///     log.lock().unwrap().push(format!("after {} {}", table, record["id"]));
+    /// });
+    ///
+    /// db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+    /// assert_eq!(*audit.lock().unwrap(), vec!["before Client", "after Client 1"]);
+    /// ```
+    pub fn on_before_insert(&self, hook: impl Fn(&str, &serde_json::Value) + Send + Sync + 'static) {
+        Hooks::push(&self.hooks.before_insert, Arc::new(hook));
+    }
+
+    /// Registers a callback run after each record is inserted
+    ///
+    /// The record passed to the callback carries the primary key it was given,
+    /// including for the multi-row inserts of [`Kingler::insert_iter`].
+    pub fn on_after_insert(&self, hook: impl Fn(&str, &serde_json::Value) + Send + Sync + 'static) {
+        Hooks::push(&self.hooks.after_insert, Arc::new(hook));
+    }
+
+    /// Registers a callback run before each record is updated by its primary key
+    ///
+    /// Bulk updates such as [`Kingler::update_where`] don't fire it.
+    pub fn on_before_update(&self, hook: impl Fn(&str, &serde_json::Value) + Send + Sync + 'static) {
+        Hooks::push(&self.hooks.before_update, Arc::new(hook));
+    }
+
+    /// Registers a callback run after each record is updated by its primary key
+    pub fn on_after_update(&self, hook: impl Fn(&str, &serde_json::Value) + Send + Sync + 'static) {
+        Hooks::push(&self.hooks.after_update, Arc::new(hook));
+    }
+
+    /// Registers a callback run before a record is deleted by its primary key
+    ///
+    /// The callback receives an object holding only the primary key, e.g.
+    /// `{"id": 1}`. It fires for [`Kingler::delete_by_id`], [`Kingler::delete_by_ids`],
+    /// [`Kingler::delete_returning`] and each record emptied by [`Kingler::delete_all`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// # use std::sync::{Arc, Mutex};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_hooks_delete.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    ///
+    /// let audit = Arc::new(Mutex::new(Vec::new()));
+    /// let log = audit.clone();
+    /// db.on_before_delete(move |table, key| log.lock().unwrap().push(format!("before {} {}", table, key)));
+    /// let log = audit.clone();
+    /// db.on_after_delete(move |table, key| log.lock().unwrap().push(format!("after {} {}", table, key)));
+    ///
+    /// let first = db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+    /// let second = db.insert(&Client { id: None, name: "Jane Doe".to_string() }).unwrap();
+    /// db.delete_by_id::<Client>(first).unwrap();
+    /// let deleted = db.delete_returning::<Client>(second).unwrap();
+    /// assert_eq!(deleted.unwrap().name, "Jane Doe");
+    /// db.insert(&Client { id: None, name: "Richard Roe".to_string() }).unwrap();
+    /// assert_eq!(db.delete_all::<Client>(false).unwrap(), 1);
+    ///
+    /// assert_eq!(*audit.lock().unwrap(), vec![
+    ///     r#"before Client {"id":1}"#,
+    ///     r#"after Client {"id":1}"#,
+    ///     r#"before Client {"id":2}"#,
+    ///     r#"after Client {"id":2}"#,
+    ///     r#"before Client {"id":3}"#,
+    ///     r#"after Client {"id":3}"#,
+    /// ]);
+    /// ```
+    pub fn on_before_delete(&self, hook: impl Fn(&str, &serde_json::Value) + Send + Sync + 'static) {
+        Hooks::push(&self.hooks.before_delete, Arc::new(hook));
+    }
+
+    /// Registers a callback run after a record is deleted by its primary key
+    pub fn on_after_delete(&self, hook: impl Fn(&str, &serde_json::Value) + Send + Sync + 'static) {
+        Hooks::push(&self.hooks.after_delete, Arc::new(hook));
+    }
+
+    /// Registers a callback run for every row inserted, updated or deleted,
+    /// e.g. to invalidate a cache
+    ///
+    /// Unlike the record callbacks, it is SQLite's update hook: it fires for
+    /// every statement run by the handle and its transactions, bulk and raw ones
+    /// included, with the table name and the rowid of each changed row. It
+    /// fires while the statement runs, before any commit, so changes rolled
+    /// back are reported too. The callback can run on any thread, hence
+    /// `Send + Sync`, and must not use the database itself, which is still busy
+    /// with the statement. Tables created `WITHOUT ROWID` have no rowid and
+    /// don't fire it. Like the other callbacks, it can be registered through a
+    /// shared `&Kingler`, e.g. behind an `Arc`; a connection already open, such
+    /// as that of a running transaction, keeps the callbacks it was opened with.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{hooks::Action, Kingler, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// # use std::sync::{Arc, Mutex};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_hooks_on_change.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    ///
+    /// let changes = Arc::new(Mutex::new(Vec::new()));
+    /// let log = changes.clone();
+    /// db.on_change(move |action, table, rowid| log.lock().unwrap().push((action, table.to_string(), rowid)));
+    ///
+    /// let id = db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+    /// db.update_all::<Client>(&[("name", KinglerValue::from("Jane Doe"))]).unwrap();
+    /// db.delete_by_id::<Client>(id.clone()).unwrap();
+    ///
+    /// let rowid = id.as_i64().unwrap();
+    /// assert_eq!(*changes.lock().unwrap(), vec![
+    ///     (Action::Insert, "Client".to_string(), rowid),
+    ///     (Action::Update, "Client".to_string(), rowid),
+    ///     (Action::Delete, "Client".to_string(), rowid),
+    /// ]);
+    /// ```
+    pub fn on_change(&self, hook: impl Fn(Action, &str, i64) + Send + Sync + 'static) {
+        Hooks::push(&self.hooks.change, Arc::new(hook));
+    }
+}
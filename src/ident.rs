@@ -0,0 +1,133 @@
+use std::fmt;
+use std::ops::Deref;
+
+use crate::{Dialect, KinglerError};
+
+/// A table or column name checked once to be a safe SQL identifier
+///
+/// Names are made of ASCII letters, digits and underscores and don't start with
+/// a digit. [`Ident::new`] is the one place enforcing this rule: the query,
+/// aggregate and index methods taking a column or index name take anything
+/// converting into an `Ident`, such as `&str` or `&Ident`, and fail on a name
+/// that doesn't convert. Building an `Ident` from untrusted input, such as a
+/// sort column picked in a web request, rejects a bad name up front instead of
+/// at the query using it. Methods taking a column of a struct still check that
+/// the table has it.
+///
+/// # Example
+/// ```rust
+/// # use kingler::{Database, Ident, Kingler, KinglerError, Query, Table};
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize, Table)]
+/// struct Client {
+///     id: Option<i64>,
+///     name: String,
+/// }
+///
+/// let column = Ident::new("name").unwrap();
+/// assert_eq!(column.as_str(), "name");
+/// assert_eq!(column.quoted(&Database::Sqlite), r#""name""#);
+///
+/// for invalid in ["", "1st", "name; DROP TABLE Client", "first name", "naïve"] {
+///     assert!(matches!(Ident::new(invalid), Err(KinglerError::InvalidIdentifier(name)) if name == invalid));
+/// }
+///
+/// # let path = std::env::temp_dir().join("kingler_ident.db");
+/// # let _ = std::fs::remove_file(&path);
+/// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+/// db.create_table(Client { id: None, name: String::new() }).unwrap();
+/// db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+/// let clients = Query::<Client>::new().order_by(&column).fetch(&db).unwrap();
+/// assert_eq!(clients.len(), 1);
+/// assert_eq!(db.count_distinct::<Client>(&column).unwrap(), 1);
+/// db.create_index::<Client>(&Ident::new("client_name").unwrap(), &[&column], None).unwrap();
+///
+/// let bad = Query::<Client>::new().order_by("name; DROP TABLE Client").fetch(&db);
+/// assert!(matches!(bad, Err(KinglerError::InvalidIdentifier(_))));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Ident(String);
+
+impl Ident {
+    /// Checks that a name is a safe identifier
+    ///
+    /// # Errors
+    /// Returns `KinglerError::InvalidIdentifier` if it isn't.
+    pub fn new(name: &str) -> Result<Ident, KinglerError> {
+        let mut chars = name.chars();
+        let is_valid = match chars.next() {
+            Some(first) => {
+                (first.is_ascii_alphabetic() || first == '_')
+                    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            }
+            None => false,
+        };
+        if is_valid {
+            Ok(Ident(name.to_string()))
+        } else {
+            Err(KinglerError::InvalidIdentifier(name.to_string()))
+        }
+    }
+
+    /// The name, as given
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The name quoted for the dialect
+    pub fn quoted(&self, dialect: &dyn Dialect) -> String {
+        dialect.quote_ident(&self.0)
+    }
+}
+
+impl Deref for Ident {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Ident {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl TryFrom<&str> for Ident {
+    type Error = KinglerError;
+
+    fn try_from(name: &str) -> Result<Ident, KinglerError> {
+        Ident::new(name)
+    }
+}
+
+impl TryFrom<String> for Ident {
+    type Error = KinglerError;
+
+    fn try_from(name: String) -> Result<Ident, KinglerError> {
+        Ident::new(&name)
+    }
+}
+
+impl TryFrom<&String> for Ident {
+    type Error = KinglerError;
+
+    fn try_from(name: &String) -> Result<Ident, KinglerError> {
+        Ident::new(name)
+    }
+}
+
+impl TryFrom<&Ident> for Ident {
+    type Error = KinglerError;
+
+    fn try_from(ident: &Ident) -> Result<Ident, KinglerError> {
+        Ok(ident.clone())
+    }
+}
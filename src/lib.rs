@@ -1,17 +1,155 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
 use serde::Serialize;
-use rusqlite;
+pub mod blob;
+pub mod builder;
+pub mod column;
+pub mod database;
+pub mod dialect;
+pub mod error;
+pub mod functions;
+pub mod hooks;
+pub mod ident;
+pub mod query;
+pub mod result_set;
+pub mod schema;
+pub mod sql;
+pub mod sql_type;
 pub mod sqlite;
+pub mod transaction;
+pub mod validate;
+pub mod value;
+
+pub use blob::Blob;
+pub use builder::KinglerBuilder;
+pub use column::Column;
+pub use database::Database;
+pub use dialect::Dialect;
+pub use error::KinglerError;
+pub use ident::Ident;
+pub use kingler_derive::Table;
+pub use query::Query;
+pub use result_set::ResultSet;
+pub use sql_type::SqlType;
+pub use transaction::Transaction;
+pub use validate::Validate;
+pub use value::KinglerValue;
+
+use functions::Functions;
+use hooks::Hooks;
+
+// Lets the code generated by `#[derive(Table)]` refer to `::kingler` from within this crate
+extern crate self as kingler;
+
+/// Dependencies used by the code generated by `#[derive(Table)]`, not public API
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json;
+}
+
+/// Records written by each statement of [`Kingler::insert_iter`] unless
+/// configured with [`KinglerBuilder::insert_chunk_size`]
+pub const DEFAULT_INSERT_CHUNK_SIZE: usize = 100;
+
+/// Most parameters SQLite binds to a single statement (3.32 and later)
+const MAX_VARIABLES: usize = 32766;
 
+/// Quoted columns written by an insert, the values bound to them and the
+/// record's primary key, if it has a primary key field
+type InsertValues = (Vec<String>, Vec<KinglerValue>, Option<KinglerValue>);
+
+/// How the names of the tables derived from structs are spelled, shared by
+/// every operation so creating and querying a table agree on its name
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TableNaming {
+    /// Prefix prepended to every table name, see [`KinglerBuilder::table_prefix`]
+    pub(crate) prefix: String,
+    /// Whether names are folded to lowercase, see
+    /// [`KinglerBuilder::lowercase_table_names`]
+    pub(crate) lowercase: bool,
+}
+
+/// What [`Kingler::create_table_with_mode`] does when the table already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CreateMode {
+    /// Leaves the existing table as it is, whatever its columns
+    /// (`CREATE TABLE IF NOT EXISTS`), as [`Kingler::create_table`] does
+    #[default]
+    IfNotExists,
+    /// Drops the existing table and its records, then creates it again
+    Replace,
+    /// Fails with `KinglerError::TableExists`
+    Strict,
+}
+
+/// A Rust struct that is stored as a database table
+///
+/// Usually implemented with `#[derive(Table)]`, which names the table after the
+/// struct and generates the column metadata from its fields.
 pub trait Table {
+    /// Name of the database table
     fn table_name() -> &'static str;
+    /// Names of the table's columns
     fn to_columns(&self) -> Vec<String>;
+    /// Column metadata used to generate the table schema
+    ///
+    /// Defaults to no metadata, in which case the columns are inferred from the
+    /// serialized record instead.
+    fn columns() -> Vec<Column> {
+        Vec::new()
+    }
+    /// Name of the primary key column, used by the by-id operations
+    fn primary_key() -> &'static str {
+        "id"
+    }
+    /// Sets of columns whose combined values must be unique, rendered as
+    /// table-level `UNIQUE (a, b)` constraints
+    fn unique_constraints() -> Vec<Vec<&'static str>> {
+        Vec::new()
+    }
+    /// Whether the table is created `WITHOUT ROWID`, stored in the order of
+    /// its primary key
+    fn without_rowid() -> bool {
+        false
+    }
 }
-#[derive(Serialize)]
-
 
+/// Handle to a database managed by the ORM
+/// 
+/// The handle holds the connection URI, which may contain credentials, so it
+/// deliberately doesn't implement `Serialize`:
+/// ```compile_fail
+/// fn assert_serialize<T: serde::Serialize>() {}
+/// assert_serialize::<kingler::Kingler>();
+/// ```
 pub struct Kingler {
     database: String,
     uri: String,
+    /// Separate database that reads are routed to, e.g. a read replica
+    read_uri: Option<String>,
+    /// How the table names derived from structs are spelled
+    naming: TableNaming,
+    /// Where the last statement run is recorded when SQL capture is enabled
+    sql_log: Option<sqlite::SqlLog>,
+    /// Whether the parent directory of a database file is created when missing
+    create_dirs: bool,
+    /// Most records written by each statement of `insert_iter`
+    insert_chunk_size: usize,
+    /// Callbacks run around writes, shared with the handle's transactions
+    hooks: Arc<Hooks>,
+    /// Scalar functions added to every connection
+    functions: Functions,
+    /// Whether empty strings are written to text columns as NULL
+    empty_string_as_null: bool,
+    /// Whether connections enforce foreign keys
+    foreign_keys: bool,
+    /// Page cache of each connection in KiB, SQLite's default when unset
+    cache_size_kb: Option<u32>,
+    /// Key unlocking an encrypted database
+    #[cfg(feature = "sqlcipher")]
+    encryption_key: Option<String>,
 }
 
 impl Kingler {
@@ -23,180 +161,3803 @@ impl Kingler {
     /// 
     /// # Example
     /// ```rust
+    /// # use kingler::Kingler;
     /// let db = Kingler::new("sqlite".to_string(), "my_database.db".to_string());
     /// ```
     pub fn new(database: String, uri: String) -> Self {
         Kingler {
             database,
             uri,
+            read_uri: None,
+            naming: TableNaming::default(),
+            sql_log: None,
+            create_dirs: false,
+            insert_chunk_size: DEFAULT_INSERT_CHUNK_SIZE,
+            hooks: Arc::default(),
+            functions: Functions::default(),
+            empty_string_as_null: false,
+            foreign_keys: false,
+            cache_size_kb: None,
+            #[cfg(feature = "sqlcipher")]
+            encryption_key: None,
+        }
+    }
+
+    /// Creates a new instance of the Kingler ORM that reads and writes through
+    /// different databases, e.g. a read replica and its primary
+    /// 
+    /// `find_*` methods and queries use `read_uri`; table creation, inserts,
+    /// updates and deletes use `write_uri`. Keeping the read database in sync
+    /// is left to the database setup.
+    /// 
+    /// # Arguments
+    /// * `database` - The type of database ("sqlite" or "mysql")
+    /// * `read_uri` - The connection string or file path used for reads
+    /// * `write_uri` - The connection string or file path used for writes
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let read_path = std::env::temp_dir().join("kingler_with_read_write_read.db");
+    /// # let write_path = std::env::temp_dir().join("kingler_with_read_write_write.db");
+    /// # let _ = std::fs::remove_file(&read_path);
+    /// # let _ = std::fs::remove_file(&write_path);
+    /// let read_uri = read_path.to_string_lossy().to_string();
+    /// let write_uri = write_path.to_string_lossy().to_string();
+    /// let replica = Kingler::new("sqlite".to_string(), read_uri.clone());
+    /// let primary = Kingler::new("sqlite".to_string(), write_uri.clone());
+    /// replica.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// primary.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// 
+    /// let db = Kingler::with_read_write("sqlite".to_string(), read_uri, write_uri);
+    /// db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+    /// 
+    /// assert_eq!(primary.find_all::<Client>().unwrap().len(), 1);
+    /// assert!(db.find_all::<Client>().unwrap().is_empty());
+    /// ```
+    pub fn with_read_write(database: String, read_uri: String, write_uri: String) -> Self {
+        Self {
+            read_uri: Some(read_uri),
+            ..Self::new(database, write_uri)
         }
     }
 
     /// Internal helper function that converts a Rust struct into database column definitions
     /// 
+    /// Uses the column metadata of the `Table` implementation when available, in
+    /// the order the fields are declared. Otherwise the columns are inferred from
+    /// the serialized value with the `id` column first.
+    /// 
     /// # Type Parameters
-    /// * `T` - Any type that implements the Serialize trait
+    /// * `T` - Any type that implements the Table and Serialize traits
     /// 
     /// # Arguments
+    /// * `database` - The backend whose dialect spells the column types
     /// * `value` - The struct instance to analyze
     /// 
     /// # Returns
-    /// A vector of tuples containing column names and their SQL types
-    fn generate_columns<T: Serialize>(value: T) -> Vec<(String, String)> {
+    /// A vector of tuples containing column names and their SQL definitions
+    fn generate_columns<T: Table + Serialize>(database: Database, value: T) -> Vec<(String, String)> {
         let mut columns = Vec::new();
+
+        // Column metadata keeps the struct's field order, including where the id appears
+        let table_columns = Self::metadata_columns::<T>(database);
+        if !table_columns.is_empty() {
+            return table_columns;
+        }
         
-        if let Ok(json_value) = serde_json::to_value(&value) {
-            if let serde_json::Value::Object(map) = json_value {
-                // Handle ID field first
-                if map.contains_key("id") {
-                    columns.push(("id".to_string(), "INTEGER PRIMARY KEY AUTOINCREMENT".to_string()));
-                }
-                
-                // Handle other fields
-                for (field_name, field_value) in map {
-                    if field_name != "id" {  // Skip id as it's already handled
-                        let sql_type = match field_value {
-                            serde_json::Value::String(_) => "TEXT",
-                            serde_json::Value::Number(_) => "INTEGER",
-                            serde_json::Value::Bool(_) => "BOOLEAN",
-                            _ => "TEXT",
-                        };
-                        columns.push((field_name, sql_type.to_string()));
-                    }
+        if let Ok(serde_json::Value::Object(map)) = serde_json::to_value(&value) {
+            // Handle ID field first
+            if map.contains_key("id") {
+                columns.push(("id".to_string(), database.id_column().to_string()));
+            }
+            
+            // Handle other fields
+            for (field_name, field_value) in map {
+                if field_name != "id" {  // Skip id as it's already handled
+                    let sql_type = match field_value {
+                        serde_json::Value::String(_) => SqlType::Text,
+                        serde_json::Value::Number(_) => SqlType::Integer,
+                        serde_json::Value::Bool(_) => SqlType::Boolean,
+                        _ => SqlType::Text,
+                    };
+                    columns.push((field_name, database.render(sql_type)));
                 }
             }
         }
         columns
     }
 
-    fn format_columns(columns: Vec<(String, String)>) -> Vec<String> {
-        columns.into_iter()
-            .map(|(name, type_)| format!("{} {}", name, type_))
+    /// Internal helper function that returns the name and definition of each
+    /// column of the column metadata of `T`
+    fn metadata_columns<T: Table>(database: Database) -> Vec<(String, String)> {
+        T::columns().into_iter()
+            .map(|column| (column.name.to_string(), column.definition_for(database)))
             .collect()
     }
 
+    /// Internal helper function that renders the column definitions and table
+    /// constraints of the table of `T`, as in `CREATE TABLE`
+    /// 
+    /// [`create_table`](Self::create_table), [`plan_schema`](Self::plan_schema)
+    /// and [`schema_hash`](Self::schema_hash) all render the table through it.
+    fn column_definitions<T: Table>(database: Database, columns: Vec<(String, String)>) -> Vec<String> {
+        let mut definitions: Vec<String> = columns.into_iter()
+            .map(|(name, type_)| format!("{} {}", database.quote_ident(&name), type_))
+            .collect();
+        for unique in T::unique_constraints() {
+            let unique: Vec<String> = unique.iter().map(|column| database.quote_ident(column)).collect();
+            definitions.push(format!("UNIQUE ({})", unique.join(", ")));
+        }
+        definitions
+    }
+
+    /// Internal helper function that returns the name of the table of `T` as
+    /// spelled by the given naming policy
+    fn table_name<T: Table>(naming: &TableNaming) -> String {
+        let table_name = format!("{}{}", naming.prefix, T::table_name());
+        if naming.lowercase {
+            table_name.to_lowercase()
+        } else {
+            table_name
+        }
+    }
+
+    /// Opens a connection to the configured database, used for writes
+    fn connect(&self) -> Result<sqlite::Sqlite, KinglerError> {
+        self.connect_to(&self.uri)
+    }
+
+    /// Opens a connection for reads, to the read database when one is configured
+    fn connect_read(&self) -> Result<sqlite::Sqlite, KinglerError> {
+        self.connect_to(self.read_uri.as_ref().unwrap_or(&self.uri))
+    }
+
+    /// Opens a connection to the given URI of the configured database type
+    fn connect_to(&self, uri: &str) -> Result<sqlite::Sqlite, KinglerError> {
+        match self.database.as_str() {
+            "sqlite" => {
+                if self.create_dirs {
+                    if let Some(parent) = std::path::Path::new(uri).parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                }
+                let mut sqlite = sqlite::Sqlite::new(uri.to_string())?;
+                #[cfg(feature = "sqlcipher")]
+                if let Some(key) = &self.encryption_key {
+                    sqlite.set_key(key).map_err(|err| match err.sqlite_error_code() {
+                        Some(rusqlite::ErrorCode::NotADatabase) => KinglerError::InvalidEncryptionKey,
+                        _ => KinglerError::from(err),
+                    })?;
+                }
+                if self.foreign_keys {
+                    sqlite.connection().execute_batch("PRAGMA foreign_keys = ON")?;
+                }
+                if let Some(cache_size_kb) = self.cache_size_kb {
+                    // A negative size is in KiB rather than in pages
+                    sqlite.connection().execute_batch(&format!("PRAGMA cache_size = -{}", cache_size_kb))?;
+                }
+                self.functions.register(sqlite.connection())?;
+                self.hooks.register(sqlite.connection());
+                sqlite.sql_log = self.sql_log.clone();
+                Ok(sqlite)
+            }
+            _ => Err(KinglerError::UnsupportedDatabase(self.database.to_string())),
+        }
+    }
+
+    /// Returns the last statement run and the parameters bound to it
+    /// 
+    /// Only available when the handle was built with
+    /// [`KinglerBuilder::capture_sql`], otherwise `None` is returned. Statements
+    /// run by transactions opened from this handle are recorded as well, while
+    /// transaction control and maintenance commands (`BEGIN`, `VACUUM`, ...) are not.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_last_sql.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::builder("sqlite".to_string(), path.to_string_lossy().to_string())
+    ///     .capture_sql(true)
+    ///     .build()
+    ///     .unwrap();
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+    /// 
+    /// let (sql, params) = db.last_sql().unwrap();
+    /// assert_eq!(sql, r#"INSERT INTO "Client" ("name") VALUES (?) RETURNING "id""#);
+    /// assert_eq!(params, vec![KinglerValue::from("John Doe")]);
+    /// ```
+    pub fn last_sql(&self) -> Option<(String, Vec<KinglerValue>)> {
+        let sql_log = self.sql_log.as_ref()?;
+        sql_log.lock().unwrap_or_else(std::sync::PoisonError::into_inner).clone()
+    }
+
+    /// Checks that a name can be safely used as a table or column identifier,
+    /// see [`Ident`]
+    fn validate_identifier(name: &str) -> Result<(), KinglerError> {
+        Ident::new(name).map(drop)
+    }
+
+    /// Checks that a column belongs to the table of `T`, returning its name
+    /// 
+    /// Tables without column metadata only have the identifier itself validated.
+    fn validate_column<T: Table>(name: impl TryInto<Ident, Error = KinglerError>) -> Result<Ident, KinglerError> {
+        let name = name.try_into()?;
+        let columns = T::columns();
+        if !columns.is_empty() && !columns.iter().any(|column| column.name == name.as_str()) {
+            return Err(KinglerError::UnknownColumn(name.to_string()));
+        }
+        Ok(name)
+    }
+
     /// Creates a new database table based on a Rust struct
     /// 
     /// # Type Parameters
-    /// * `T` - Any type that implements the Serialize trait
+    /// * `T` - Any type that implements the Table and Serialize traits
     /// 
     /// # Arguments
     /// * `value` - An instance of the struct to use as a template
     /// 
     /// # Example
     /// ```rust
-    /// #[derive(Serialize)]
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::Serialize;
+    /// #[derive(Serialize, Table)]
     /// struct User {
     ///     name: String,
     ///     age: i32,
     /// }
     /// 
-    /// let db = Kingler::new("sqlite".to_string(), "my_database.db".to_string());
+    /// # let path = std::env::temp_dir().join("kingler_create_table.db");
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
     /// db.create_table(User {
     ///     name: String::new(),
     ///     age: 0,
-    /// });
+    /// }).unwrap();
     /// ```
-    pub fn create_table<T: Serialize>(&self, value: T) -> Result<(), rusqlite::Error> {
-        let table_name = std::any::type_name::<T>()
-            .split("::")
-            .last()
-            .unwrap_or("unknown");
-        
-        println!("Creating table for {}", table_name);
-        
-        let columns = Self::generate_columns(value);
-        let formatted_columns = Self::format_columns(columns);
-        
-        match self.database.as_str() {
-            "sqlite" => {
-                if let Ok(sqlite) = sqlite::Sqlite::new(self.uri.to_string()) {
-                    return sqlite.create_table(table_name.to_string(), formatted_columns);
+    /// 
+    /// Columns are created in the order the fields are declared:
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::Serialize;
+    /// #[derive(Serialize, Table)]
+    /// struct Account {
+    ///     name: String,
+    ///     id: Option<i64>,
+    ///     balance: f64,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_create_table_order.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Account { name: String::new(), id: None, balance: 0.0 }).unwrap();
+    /// 
+    /// let conn = rusqlite::Connection::open(&path).unwrap();
+    /// let ddl: String = conn.query_row(
+    ///     "SELECT sql FROM sqlite_master WHERE name = 'Account'", [], |row| row.get(0)
+    /// ).unwrap();
+    /// assert_eq!(
+    ///     ddl,
+    ///     r#"CREATE TABLE "Account" ("name" TEXT, "id" INTEGER PRIMARY KEY AUTOINCREMENT, "balance" REAL)"#
+    /// );
+    /// ```
+    /// 
+    /// `#[table(unique(...))]` adds a constraint on the combination of several columns:
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::Serialize;
+    /// #[derive(Serialize, Table)]
+    /// #[table(unique(room, day))]
+    /// struct Booking {
+    ///     id: Option<i64>,
+    ///     room: i64,
+    ///     day: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_create_table_unique.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// # let booking = |room, day: &str| Booking { id: None, room, day: day.to_string() };
+    /// db.create_table(booking(0, "")).unwrap();
+    /// 
+    /// db.insert(&booking(1, "monday")).unwrap();
+    /// db.insert(&booking(1, "tuesday")).unwrap();
+    /// db.insert(&booking(2, "monday")).unwrap();
+    /// assert!(db.insert(&booking(1, "monday")).is_err());
+    /// ```
+    /// 
+    /// `#[table(without_rowid)]` creates a table clustered on its primary key,
+    /// which suits natural keys:
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// #[table(without_rowid)]
+    /// struct Currency {
+    ///     #[column(primary_key)]
+    ///     code: String,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_create_table_without_rowid.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Currency { code: String::new(), name: String::new() }).unwrap();
+    /// 
+    /// let code = db.insert(&Currency { code: "PEN".to_string(), name: "Sol".to_string() }).unwrap();
+    /// assert_eq!(code, KinglerValue::Text("PEN".to_string()));
+    /// assert_eq!(db.find_by_id::<Currency>("PEN").unwrap().unwrap().name, "Sol");
+    /// 
+    /// let conn = rusqlite::Connection::open(&path).unwrap();
+    /// let ddl: String = conn.query_row(
+    ///     "SELECT sql FROM sqlite_master WHERE name = 'Currency'", [], |row| row.get(0)
+    /// ).unwrap();
+    /// assert!(ddl.ends_with("WITHOUT ROWID"));
+    /// ```
+    /// 
+    /// Table and column names are always quoted, so fields named after SQL
+    /// keywords work in every operation:
+    /// ```rust
+    /// # use kingler::{Kingler, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Shipment {
+    ///     id: Option<i64>,
+    ///     order: i64,
+    ///     select: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_create_table_reserved_words.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Shipment { id: None, order: 0, select: String::new() }).unwrap();
+    /// db.insert(&Shipment { id: None, order: 2, select: "express".to_string() }).unwrap();
+    /// db.insert(&Shipment { id: None, order: 1, select: "standard".to_string() }).unwrap();
+    /// 
+    /// let shipments = Query::<Shipment>::new().filter("select", "express").fetch(&db).unwrap();
+    /// assert_eq!(shipments[0].order, 2);
+    /// let ordered = Query::<Shipment>::new().order_by("order").fetch(&db).unwrap();
+    /// assert_eq!(ordered[0].select, "standard");
+    /// assert_eq!(db.find_where::<Shipment>(&[("order", 1.into())]).unwrap().len(), 1);
+    /// 
+    /// // The same goes for table names
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Order {
+    ///     id: Option<i64>,
+    ///     total: i64,
+    /// }
+    /// 
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Group {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// db.create_table(Order { id: None, total: 0 }).unwrap();
+    /// db.create_table(Group { id: None, name: String::new() }).unwrap();
+    /// db.insert(&Order { id: None, total: 42 }).unwrap();
+    /// assert_eq!(db.find_all_dynamic("Order").unwrap().len(), 1);
+    /// db.create_relationship::<Order, Group>("ONE_TO_MANY").unwrap();
+    /// db.create_relationship::<Order, Group>("ONE_TO_ONE").unwrap();
+    /// db.create_relationship::<Order, Group>("MANY_TO_MANY").unwrap();
+    /// assert_eq!(db.find_all::<Order>().unwrap()[0].total, 42);
+    /// ```
+    /// 
+    /// Such a table must declare its primary key:
+    /// ```compile_fail
+    /// # use kingler::Table;
+    /// #[derive(Table)]
+    /// #[table(without_rowid)]
+    /// struct Tag {
+    ///     name: String,
+    /// }
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::MissingPrimaryKey` for a `WITHOUT ROWID` table
+    /// whose column metadata has no primary key.
+    pub fn create_table<T: Table + Serialize>(&self, value: T) -> Result<(), KinglerError> {
+        self.create_table_with_mode(value, CreateMode::IfNotExists)
+    }
+
+    /// Creates the table of a struct, choosing what happens when it exists
+    /// 
+    /// [`create_table`](Self::create_table) leaves an existing table alone even
+    /// when its columns differ, which hides schema drift. [`CreateMode::Strict`]
+    /// refuses to run against an existing table instead, while
+    /// [`CreateMode::Replace`] drops it and its records first, which suits
+    /// development databases. The table is dropped and created in a single
+    /// transaction, so a failed creation keeps the old table.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{CreateMode, Kingler, KinglerError, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_create_table_with_mode.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// # let client = || Client { id: None, name: "John Doe".to_string() };
+    /// db.create_table_with_mode(client(), CreateMode::Strict).unwrap();
+    /// db.insert(&client()).unwrap();
+    /// 
+    /// db.create_table_with_mode(client(), CreateMode::IfNotExists).unwrap();
+    /// assert_eq!(db.find_all::<Client>().unwrap().len(), 1);
+    /// 
+    /// let result = db.create_table_with_mode(client(), CreateMode::Strict);
+    /// assert!(matches!(result, Err(KinglerError::TableExists(table)) if table == "Client"));
+    /// assert_eq!(db.find_all::<Client>().unwrap().len(), 1);
+    /// 
+    /// db.create_table_with_mode(client(), CreateMode::Replace).unwrap();
+    /// assert!(db.find_all::<Client>().unwrap().is_empty());
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::TableExists` in [`CreateMode::Strict`] if the
+    /// table exists, and fails as [`create_table`](Self::create_table) does.
+    pub fn create_table_with_mode<T: Table + Serialize>(&self, value: T, mode: CreateMode) -> Result<(), KinglerError> {
+        let table_name = Self::table_name::<T>(&self.naming);
+        let sqlite = self.connect()?;
+        match mode {
+            CreateMode::IfNotExists => Self::create_table_with(&sqlite, &table_name, value, false),
+            CreateMode::Strict => {
+                if sqlite.table_exists(&table_name)? {
+                    return Err(KinglerError::TableExists(table_name));
                 }
-                Ok(())
+                Self::create_table_with(&sqlite, &table_name, value, false)
             }
-            "mysql" => {
-                println!("MySQL database not supported yet");
-                Err(rusqlite::Error::ExecuteReturnedResults)
-            }
-            _ => {
-                eprintln!("Database {} not supported", self.database);
-                Err(rusqlite::Error::ExecuteReturnedResults)
+            CreateMode::Replace => {
+                sqlite.begin()?;
+                let replaced = sqlite.drop_table(sqlite.database().quote_ident(&table_name))
+                    .map_err(KinglerError::from)
+                    .and_then(|()| Self::create_table_with(&sqlite, &table_name, value, false));
+                match replaced {
+                    Ok(()) => Ok(sqlite.commit()?),
+                    Err(err) => {
+                        sqlite.rollback()?;
+                        Err(err)
+                    }
+                }
             }
         }
     }
 
+    /// Internal helper function that creates the table of a struct using the
+    /// given connection, unless it exists
+    /// 
+    /// A temporary table keeps no column comments, which would outlive it.
+    fn create_table_with<T: Table + Serialize>(
+        sqlite: &sqlite::Sqlite,
+        table_name: &str,
+        value: T,
+        temporary: bool,
+    ) -> Result<(), KinglerError> {
+        let database = sqlite.database();
+        let columns = Self::generate_columns(database, value);
+        let formatted_columns = Self::column_definitions::<T>(database, columns);
+        
+        if T::without_rowid() && !T::columns().iter().any(|column| column.primary_key) {
+            return Err(KinglerError::MissingPrimaryKey(table_name.to_string()));
+        }
+        if temporary {
+            sqlite.create_temp_table(database.quote_ident(table_name), formatted_columns, T::without_rowid())?;
+            return Ok(());
+        }
+        if T::without_rowid() {
+            sqlite.create_table_without_rowid(database.quote_ident(table_name), formatted_columns)?;
+        } else {
+            sqlite.create_table(database.quote_ident(table_name), formatted_columns)?;
+        }
+
+        let comments: Vec<(&str, &str)> = T::columns().iter()
+            .filter_map(|column| column.comment.map(|comment| (column.name, comment)))
+            .collect();
+        if !comments.is_empty() {
+            sqlite.set_column_comments(table_name, &comments)?;
+        }
+        Ok(())
+    }
+
+    /// Lists the columns of a table with their declared types and the comments
+    /// set with `#[column(comment = "...")]`
+    /// 
+    /// SQLite keeps no comments in the schema, so [`create_table`](Self::create_table)
+    /// stores them in the `_kingler_column_meta` table, which this reads back.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::Serialize;
+    /// #[derive(Serialize, Table)]
+    /// struct Invoice {
+    ///     id: Option<i64>,
+    ///     #[column(comment = "Total in cents, taxes included")]
+    ///     total: i64,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_describe_table.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Invoice { id: None, total: 0 }).unwrap();
+    /// 
+    /// let columns = db.describe_table::<Invoice>().unwrap();
+    /// assert_eq!(columns[1].name, "total");
+    /// assert_eq!(columns[1].sql_type, "INTEGER");
+    /// assert_eq!(columns[1].comment.as_deref(), Some("Total in cents, taxes included"));
+    /// assert!(columns[0].primary_key);
+    /// assert_eq!(columns[0].comment, None);
+    /// ```
+    pub fn describe_table<T: Table>(&self) -> Result<Vec<sqlite::ColumnDescription>, KinglerError> {
+        let table_name = Self::table_name::<T>(&self.naming);
+        let sqlite = self.connect_read()?;
+        Ok(sqlite.describe_table(&table_name)?)
+    }
+
     /// Inserts a record into the database table
     /// 
+    /// A null primary key is left out so the database assigns it. A record with nothing
+    /// else to insert is stored with the column defaults (`DEFAULT VALUES`).
+    /// 
     /// # Type Parameters
-    /// * `T` - Any type that implements the Serialize trait
+    /// * `T` - Any type that implements the Table and Serialize traits
     /// 
     /// # Arguments
     /// * `record` - The struct instance to insert
     /// 
+    /// # Returns
+    /// The primary key of the inserted record. It is read back with `RETURNING`
+    /// when SQLite supports it, so text keys and keys of `WITHOUT ROWID` tables
+    /// are returned as stored. Records without a primary key column return their
+    /// rowid.
+    /// 
+    /// Integer keys are SQLite rowids, which are signed 64-bit: declaring the id
+    /// as `Option<i64>` lets [`KinglerValue::as_i64`] hand it back to the struct
+    /// unchanged, while narrower types such as `u32` overflow on large tables.
+    /// 
     /// # Example
     /// ```rust
-    /// #[derive(Serialize)]
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::Serialize;
+    /// #[derive(Serialize, Table)]
     /// struct User {
+    ///     id: Option<i64>,
     ///     name: String,
     ///     age: i32,
     /// }
     /// 
-    /// let db = Kingler::new("sqlite".to_string(), "my_database.db".to_string());
-    /// db.insert(&User {
+    /// # let path = std::env::temp_dir().join("kingler_insert.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// # db.create_table(User { id: None, name: String::new(), age: 0 }).unwrap();
+    /// let id = db.insert(&User {
+    ///     id: None,
     ///     name: "John".to_string(),
     ///     age: 30,
-    /// });
+    /// }).unwrap();
+    /// assert_eq!(id, KinglerValue::Int(1));
     /// ```
-    pub fn insert<T: Serialize>(&self, record: &T) -> Result<i64, rusqlite::Error> {
-        let type_name = std::any::type_name::<T>();
-        let table_name = type_name.split("::").last().unwrap_or(type_name);
-        
-        match self.database.as_str() {
-            "sqlite" => {
-                if let Ok(json_value) = serde_json::to_value(&record) {
-                    if let serde_json::Value::Object(map) = json_value {
-                        let mut columns: Vec<String> = Vec::new();
-                        let mut values: Vec<String> = Vec::new();
-                        
-                        // Skip id field if it's None
-                        for (key, value) in map.iter() {
-                            if key == "id" {
-                                if let serde_json::Value::Null = value {
-                                    continue;
-                                }
-                            }
-                            columns.push(key.clone());
-                            match value {
-                                serde_json::Value::Number(n) => {
-                                    if n.is_i64() {
-                                        values.push(n.as_i64().unwrap().to_string())
-                                    } else if n.is_u64() {
-                                        values.push(n.as_u64().unwrap().to_string())
-                                    } else {
-                                        values.push(n.as_f64().unwrap().to_string())
-                                    }
-                                },
-                                serde_json::Value::String(s) => values.push(format!("'{}'", s)),
-                                serde_json::Value::Bool(b) => values.push(b.to_string()),
-                                serde_json::Value::Null => values.push("NULL".to_string()),
-                                _ => values.push(value.to_string()),
-                            }
-                        }
-                        
-                        if let Ok(sqlite) = sqlite::Sqlite::new(self.uri.to_string()) {
-                            return sqlite.insert(table_name.to_string(), columns, values);
-                        }
-                    }
+    /// 
+    /// The returned key round-trips into an `i64` id:
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Purchase {
+    ///     id: Option<i64>,
+    ///     total: f64,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_insert_i64_id.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Purchase { id: None, total: 0.0 }).unwrap();
+    /// 
+    /// // Start past the u32 range, where a narrower id would overflow
+    /// let first = u32::MAX as i64 + 1;
+    /// db.insert(&Purchase { id: Some(first), total: 1.0 }).unwrap();
+    /// let id: i64 = db.insert(&Purchase { id: None, total: 2.5 }).unwrap().as_i64().unwrap();
+    /// assert_eq!(id, first + 1);
+    /// 
+    /// let purchase = db.find_by_id::<Purchase>(id).unwrap().unwrap();
+    /// assert_eq!(purchase.id, Some(id));
+    /// assert_eq!(purchase.total, 2.5);
+    /// ```
+    /// 
+    /// Optional fields bind their inner value when set and NULL otherwise:
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Member {
+    ///     id: Option<i64>,
+    ///     nickname: Option<String>,
+    ///     age: Option<i64>,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_insert_option.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Member { id: None, nickname: None, age: None }).unwrap();
+    /// let set = db.insert(&Member { id: None, nickname: Some("x".to_string()), age: Some(30) }).unwrap();
+    /// let unset = db.insert(&Member { id: None, nickname: None, age: None }).unwrap();
+    /// 
+    /// let member = db.find_by_id::<Member>(set).unwrap().unwrap();
+    /// assert_eq!((member.nickname.as_deref(), member.age), (Some("x"), Some(30)));
+    /// let member = db.find_by_id::<Member>(unset).unwrap().unwrap();
+    /// assert_eq!((member.nickname, member.age), (None, None));
+    /// 
+    /// let conn = rusqlite::Connection::open(&path).unwrap();
+    /// let mut stmt = conn.prepare("SELECT typeof(nickname), typeof(age) FROM Member ORDER BY id").unwrap();
+    /// let types: Vec<(String, String)> = stmt
+    ///     .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+    ///     .unwrap()
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(types, vec![
+    ///     ("text".to_string(), "integer".to_string()),
+    ///     ("null".to_string(), "null".to_string()),
+    /// ]);
+    /// ```
+    /// 
+    /// A text primary key is returned as given, even without a rowid:
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::Serialize;
+    /// #[derive(Serialize, Table)]
+    /// struct Country {
+    ///     #[column(primary_key)]
+    ///     code: String,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_insert_without_rowid.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// # let conn = rusqlite::Connection::open(&path).unwrap();
+    /// # conn.execute_batch(r#"CREATE TABLE "Country" ("code" TEXT PRIMARY KEY, "name" TEXT) WITHOUT ROWID"#).unwrap();
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// let code = db.insert(&Country { code: "PE".to_string(), name: "Peru".to_string() }).unwrap();
+    /// assert_eq!(code, KinglerValue::Text("PE".to_string()));
+    /// ```
+    /// 
+    /// Records with only a null id take every column's default:
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::Serialize;
+    /// #[derive(Serialize, Table)]
+    /// struct Visit {
+    ///     id: Option<i64>,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_insert_default_values.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Visit { id: None }).unwrap();
+    /// assert_eq!(db.insert(&Visit { id: None }).unwrap(), KinglerValue::Int(1));
+    /// assert_eq!(db.insert(&Visit { id: None }).unwrap(), KinglerValue::Int(2));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::NoColumns` if the record doesn't serialize to named
+    /// fields (e.g. a tuple struct):
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::Serialize;
+    /// #[derive(Serialize)]
+    /// struct Point(i64, i64);
+    /// 
+    /// impl Table for Point {
+    ///     fn table_name() -> &'static str { "Point" }
+    ///     fn to_columns(&self) -> Vec<String> { Vec::new() }
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_insert_no_columns.db");
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// let result = db.insert(&Point(1, 2));
+    /// assert!(matches!(result, Err(KinglerError::NoColumns(table)) if table == "Point"));
+    /// ```
+    pub fn insert<T: Table + Serialize>(&self, record: &T) -> Result<KinglerValue, KinglerError> {
+        let sqlite = self.connect()?;
+        Self::insert_with(&sqlite, &self.naming, &self.hooks, self.empty_string_as_null, record)
+    }
+
+    /// Internal helper function that inserts a record using the given connection
+    fn insert_with<T: Table + Serialize>(
+        sqlite: &sqlite::Sqlite,
+        naming: &TableNaming,
+        hooks: &Hooks,
+        empty_string_as_null: bool,
+        record: &T,
+    ) -> Result<KinglerValue, KinglerError> {
+        let database = sqlite.database();
+        let table_name = Self::table_name::<T>(naming);
+        let table_columns = Self::column_index::<T>();
+        let (columns, values, key) =
+            Self::insert_values(database, &table_name, &table_columns, empty_string_as_null, record)?;
+        Hooks::fire(&hooks.before_insert, &table_name, || serde_json::to_value(record).unwrap_or_default());
+
+        let quoted_table = database.quote_ident(&table_name);
+        let key = match key {
+            // Without a key column the rowid is the only identifier
+            None => KinglerValue::Int(sqlite.insert(quoted_table, columns, values)?),
+            Some(_) if sqlite.supports_returning() => {
+                let key_column = database.quote_ident(T::primary_key());
+                sqlite.insert_returning(quoted_table, columns, values, key_column)?
+            }
+            Some(key) => {
+                let rowid = sqlite.insert(quoted_table, columns, values)?;
+                match key {
+                    KinglerValue::Null => KinglerValue::Int(rowid),
+                    key => key,
                 }
-                Err(rusqlite::Error::ExecuteReturnedResults)
             }
-            "mysql" => {
-                Err(rusqlite::Error::ExecuteReturnedResults)
+        };
+
+        Hooks::fire(&hooks.after_insert, &table_name, || Self::inserted_record(record, &key));
+        Ok(key)
+    }
+
+    /// Inserts a record unless it conflicts with an existing one, e.g. for
+    /// idempotent seed data
+    /// 
+    /// Runs `INSERT OR IGNORE`, so a record violating a unique or primary key
+    /// constraint is skipped instead of failing. The before insert hooks fire
+    /// either way, the after insert hooks only for an inserted record.
+    /// 
+    /// # Returns
+    /// The rowid of the inserted record, or `None` if it was skipped
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Country {
+    ///     id: Option<i64>,
+    ///     #[column(unique)]
+    ///     code: String,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_insert_or_ignore.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Country { id: None, code: String::new(), name: String::new() }).unwrap();
+    /// 
+    /// let peru = Country { id: None, code: "PE".to_string(), name: "Peru".to_string() };
+    /// assert_eq!(db.insert_or_ignore(&peru).unwrap(), Some(1));
+    /// assert_eq!(db.insert_or_ignore(&peru).unwrap(), None);
+    /// 
+    /// let chile = Country { id: None, code: "CL".to_string(), name: "Chile".to_string() };
+    /// let id = db.insert_or_ignore(&chile).unwrap().unwrap();
+    /// assert_eq!(db.find_by_id::<Country>(id).unwrap().unwrap().name, "Chile");
+    /// assert_eq!(db.find_all::<Country>().unwrap().len(), 2);
+    /// ```
+    pub fn insert_or_ignore<T: Table + Serialize>(&self, record: &T) -> Result<Option<i64>, KinglerError> {
+        let sqlite = self.connect()?;
+        let database = sqlite.database();
+        let table_name = Self::table_name::<T>(&self.naming);
+        let table_columns = Self::column_index::<T>();
+        let (columns, values, key) =
+            Self::insert_values(database, &table_name, &table_columns, self.empty_string_as_null, record)?;
+        Hooks::fire(&self.hooks.before_insert, &table_name, || serde_json::to_value(record).unwrap_or_default());
+
+        let rowid = sqlite.insert_or_ignore(database.quote_ident(&table_name), columns, values)?;
+        if let Some(rowid) = rowid {
+            let key = match key {
+                Some(KinglerValue::Null) | None => KinglerValue::Int(rowid),
+                Some(key) => key,
+            };
+            Hooks::fire(&self.hooks.after_insert, &table_name, || Self::inserted_record(record, &key));
+        }
+        Ok(rowid)
+    }
+
+    /// Internal helper function that serializes an inserted record for the
+    /// after insert hooks, with the primary key it was stored under
+    fn inserted_record<T: Table + Serialize>(record: &T, key: &KinglerValue) -> serde_json::Value {
+        Self::keyed_record::<T>(serde_json::to_value(record).unwrap_or_default(), key)
+    }
+
+    /// Internal helper function that sets the primary key of a serialized record
+    /// to the one it was stored under
+    fn keyed_record<T: Table>(mut value: serde_json::Value, key: &KinglerValue) -> serde_json::Value {
+        if let Some(map) = value.as_object_mut() {
+            if map.contains_key(T::primary_key()) {
+                map.insert(T::primary_key().to_string(), serde_json::Value::from(key));
             }
-            _ => {
-                Err(rusqlite::Error::ExecuteReturnedResults)
+        }
+        value
+    }
+
+    /// Inserts a record, or updates the existing record it conflicts with on
+    /// the given columns
+    /// 
+    /// Runs `INSERT ... ON CONFLICT (columns) DO UPDATE`, which suits natural
+    /// keys such as an email: when a record with the same values in the
+    /// conflict columns exists, its other columns take the record's values.
+    /// The conflict columns must be covered by a unique constraint or be the
+    /// primary key. The insert hooks run either way.
+    /// 
+    /// # Returns
+    /// The primary key of the inserted or updated record, its rowid for tables
+    /// without a primary key column.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Subscriber {
+    ///     id: Option<i64>,
+    ///     #[column(unique)]
+    ///     email: String,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_upsert_on.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// # let subscriber = |email: &str, name: &str| Subscriber { id: None, email: email.to_string(), name: name.to_string() };
+    /// db.create_table(subscriber("", "")).unwrap();
+    /// 
+    /// let id = db.upsert_on(&subscriber("jane@example.com", "Jane"), &["email"]).unwrap();
+    /// db.upsert_on(&subscriber("john@example.com", "John"), &["email"]).unwrap();
+    /// let same = db.upsert_on(&subscriber("jane@example.com", "Jane Doe"), &["email"]).unwrap();
+    /// assert_eq!(same, id);
+    /// 
+    /// let subscribers = db.find_all::<Subscriber>().unwrap();
+    /// assert_eq!(subscribers.len(), 2);
+    /// assert_eq!(subscribers[0].name, "Jane Doe");
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::UnknownColumn` if a conflict column isn't a field
+    /// of `T` and `KinglerError::InvalidIdentifier` if none are given.
+    pub fn upsert_on<T: Table + Serialize>(
+        &self,
+        record: &T,
+        conflict_columns: &[&str],
+    ) -> Result<KinglerValue, KinglerError> {
+        if conflict_columns.is_empty() {
+            return Err(KinglerError::InvalidIdentifier(String::new()));
+        }
+        for column in conflict_columns {
+            Self::validate_column::<T>(*column)?;
+        }
+
+        let sqlite = self.connect()?;
+        let database = sqlite.database();
+        let table_name = Self::table_name::<T>(&self.naming);
+        let table_columns = Self::column_index::<T>();
+        let (columns, values, key) =
+            Self::insert_values(database, &table_name, &table_columns, self.empty_string_as_null, record)?;
+        Hooks::fire(&self.hooks.before_insert, &table_name, || serde_json::to_value(record).unwrap_or_default());
+
+        let quoted_table = database.quote_ident(&table_name);
+        let conflict: Vec<String> = conflict_columns.iter().map(|column| database.quote_ident(column)).collect();
+        // Without a key column the rowid is the only identifier
+        let key_column = match key {
+            Some(_) => database.quote_ident(T::primary_key()),
+            None => "rowid".to_string(),
+        };
+        let key = if sqlite.supports_returning() {
+            sqlite.upsert_returning(quoted_table, columns, values, conflict, key_column)?
+        } else {
+            // The record is found again by the values of its conflict columns
+            let conditions: Vec<String> = conflict.iter().map(|column| format!("{} = ?", column)).collect();
+            let params: Vec<KinglerValue> = conflict.iter()
+                .map(|column| {
+                    columns.iter()
+                        .position(|written| written == column)
+                        .map_or(KinglerValue::Null, |index| values[index].clone())
+                })
+                .collect();
+            sqlite.upsert(quoted_table.clone(), columns, values, conflict)?;
+            let query = sql::build_select(&quoted_table, &[key_column], &conditions);
+            sqlite.query_values(&query, params)?
+                .into_iter()
+                .next()
+                .and_then(|row| row.into_values().next())
+                .unwrap_or(KinglerValue::Null)
+        };
+
+        Hooks::fire(&self.hooks.after_insert, &table_name, || Self::inserted_record(record, &key));
+        Ok(key)
+    }
+
+    /// Internal helper function that lists the quoted columns written by an
+    /// insert of the record, the values bound to them and its primary key, if
+    /// the record has a primary key field
+    /// 
+    /// A null primary key is skipped so the database assigns it, as are
+    /// generated columns, which the database computes.
+    fn insert_values<T: Table + Serialize>(
+        database: Database,
+        table_name: &str,
+        table_columns: &HashMap<&'static str, Column>,
+        empty_string_as_null: bool,
+        record: &T,
+    ) -> Result<InsertValues, KinglerError> {
+        let serde_json::Value::Object(map) = serde_json::to_value(record)? else {
+            return Err(KinglerError::NoColumns(table_name.to_string()));
+        };
+        let mut columns: Vec<String> = Vec::new();
+        let mut values: Vec<KinglerValue> = Vec::new();
+        for (key, value) in map.iter() {
+            if (key == T::primary_key() && value.is_null()) || Self::is_generated(table_columns, key) {
+                continue;
             }
+            columns.push(database.quote_ident(key));
+            values.push(Self::column_value(table_columns, key, value, empty_string_as_null)?);
         }
+        let key = map.get(T::primary_key())
+            .map(|value| Self::column_value(table_columns, T::primary_key(), value, false))
+            .transpose()?;
+        Ok((columns, values, key))
     }
-}
\ No newline at end of file
+
+    /// Finds every record of a table whose columns equal the given values
+    /// 
+    /// Comparisons follow the column definitions, so a column declared with
+    /// `#[column(collate = "nocase")]` matches regardless of case.
+    /// 
+    /// # Type Parameters
+    /// * `T` - Any type that implements the Table and DeserializeOwned traits
+    /// 
+    /// # Arguments
+    /// * `filters` - Column names paired with the value they must be equal to.
+    ///   Every filter must match (they are combined with AND). Filtering with
+    ///   `KinglerValue::Null` matches the records where the column is NULL.
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::UnknownColumn` if a filter names a column that is
+    /// not part of the table.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct User {
+    ///     id: Option<i64>,
+    ///     #[column(unique, collate = "nocase")]
+    ///     email: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_find_where.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(User { id: None, email: String::new() }).unwrap();
+    /// db.insert(&User { id: None, email: "User@Example.com".to_string() }).unwrap();
+    /// 
+    /// let users: Vec<User> = db.find_where(&[("email", KinglerValue::from("user@example.com"))]).unwrap();
+    /// assert_eq!(users.len(), 1);
+    /// assert_eq!(users[0].email, "User@Example.com");
+    /// 
+    /// // The unique constraint uses the same collation
+    /// assert!(db.insert(&User { id: None, email: "USER@EXAMPLE.COM".to_string() }).is_err());
+    /// ```
+    /// 
+    /// Null filters match NULL columns:
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Contact {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     phone: Option<String>,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_find_where_null.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Contact { id: None, name: String::new(), phone: None }).unwrap();
+    /// db.insert(&Contact { id: None, name: "John".to_string(), phone: None }).unwrap();
+    /// db.insert(&Contact { id: None, name: "Jane".to_string(), phone: Some("555-0100".to_string()) }).unwrap();
+    /// 
+    /// let without_phone: Vec<Contact> = db.find_where(&[("phone", KinglerValue::Null)]).unwrap();
+    /// assert_eq!(without_phone.len(), 1);
+    /// assert_eq!(without_phone[0].name, "John");
+    /// ```
+    /// 
+    /// Enums marked with `#[column(enum_text)]` are stored as the name of their
+    /// variant and must hold a known variant when read back:
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    /// enum Status {
+    ///     Pending,
+    ///     Shipped,
+    ///     Delivered,
+    /// }
+    /// 
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Shipment {
+    ///     id: Option<i64>,
+    ///     #[column(enum_text)]
+    ///     status: Status,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_find_where_enum.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Shipment { id: None, status: Status::Pending }).unwrap();
+    /// for status in [Status::Pending, Status::Shipped, Status::Delivered] {
+    ///     db.insert(&Shipment { id: None, status }).unwrap();
+    /// }
+    /// 
+    /// let shipments: Vec<Shipment> = db.find_where(&[]).unwrap();
+    /// let statuses: Vec<Status> = shipments.into_iter().map(|shipment| shipment.status).collect();
+    /// assert_eq!(statuses, vec![Status::Pending, Status::Shipped, Status::Delivered]);
+    /// 
+    /// // A value written outside the ORM that isn't a variant is rejected
+    /// let conn = rusqlite::Connection::open(&path).unwrap();
+    /// conn.execute("INSERT INTO Shipment (status) VALUES ('Lost')", []).unwrap();
+    /// let result: Result<Vec<Shipment>, KinglerError> = db.find_where(&[]);
+    /// assert!(matches!(result, Err(KinglerError::InvalidEnumVariant { value, .. }) if value == "Lost"));
+    /// ```
+    pub fn find_where<T: Table + DeserializeOwned>(
+        &self,
+        filters: &[(&str, KinglerValue)],
+    ) -> Result<Vec<T>, KinglerError> {
+        let sqlite = self.connect_read()?;
+        Self::find_where_with(&sqlite, &self.naming, filters)
+    }
+
+    /// Internal helper function that finds records using the given connection
+    fn find_where_with<T: Table + DeserializeOwned>(
+        sqlite: &sqlite::Sqlite,
+        naming: &TableNaming,
+        filters: &[(&str, KinglerValue)],
+    ) -> Result<Vec<T>, KinglerError> {
+        for (column, _) in filters {
+            Self::validate_column::<T>(*column)?;
+        }
+        let database = sqlite.database();
+        let table_columns = Self::column_index::<T>();
+        let filters = filters.iter()
+            .map(|(column, value)| {
+                (database.quote_ident(column), Self::bound_value(&table_columns, column, value.clone(), false))
+            })
+            .collect();
+
+        let rows = sqlite.find_where(database.quote_ident(&Self::table_name::<T>(naming)), filters)?;
+        let columns = T::columns();
+        rows.into_iter()
+            .map(|row| Self::from_row(&columns, row))
+            .collect()
+    }
+
+    /// Finds every record of a table
+    /// 
+    /// # Type Parameters
+    /// * `T` - Any type that implements the Table and DeserializeOwned traits
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct User {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_find_all.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(User { id: None, name: String::new() }).unwrap();
+    /// db.insert(&User { id: None, name: "John".to_string() }).unwrap();
+    /// db.insert(&User { id: None, name: "Jane".to_string() }).unwrap();
+    /// 
+    /// let users: Vec<User> = db.find_all().unwrap();
+    /// assert_eq!(users.len(), 2);
+    /// ```
+    pub fn find_all<T: Table + DeserializeOwned>(&self) -> Result<Vec<T>, KinglerError> {
+        self.find_where(&[])
+    }
+
+    /// Reads the next page of records in primary key order, after the record
+    /// with the given id
+    /// 
+    /// Keyset pagination runs `WHERE id > ? ORDER BY id LIMIT ?`, which the
+    /// primary key index answers directly, so later pages cost as much as the
+    /// first one, unlike `OFFSET` which reads and skips every earlier record.
+    /// Pass `None` for the first page, then the id of the last record returned
+    /// for the next one. A page shorter than `limit` is the last.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Event {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_keyset_page.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Event { id: None, name: String::new() }).unwrap();
+    /// for i in 0..10 {
+    ///     db.insert(&Event { id: None, name: format!("event {}", i) }).unwrap();
+    /// }
+    /// 
+    /// let mut pages = Vec::new();
+    /// let mut cursor = None;
+    /// loop {
+    ///     let page = db.keyset_page::<Event>(cursor, 3).unwrap();
+    ///     if page.is_empty() {
+    ///         break;
+    ///     }
+    ///     cursor = page.last().and_then(|event| event.id);
+    ///     pages.push(page.iter().map(|event| event.id.unwrap()).collect::<Vec<_>>());
+    /// }
+    /// assert_eq!(pages, vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9], vec![10]]);
+    /// ```
+    pub fn keyset_page<T: Table + DeserializeOwned>(
+        &self,
+        after_id: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<T>, KinglerError> {
+        let sqlite = self.connect_read()?;
+        let columns = T::columns();
+        Self::keyset_rows::<T>(&sqlite, &self.naming, after_id, limit)?
+            .into_iter()
+            .map(|row| Self::from_row(&columns, row))
+            .collect()
+    }
+
+    /// Internal helper function that reads the rows of a keyset page of the
+    /// table of `T` using the given connection
+    fn keyset_rows<T: Table>(
+        sqlite: &sqlite::Sqlite,
+        naming: &TableNaming,
+        after_id: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, KinglerError> {
+        let database = sqlite.database();
+        let key = database.quote_ident(T::primary_key());
+        let (conditions, params) = match after_id {
+            Some(after_id) => (vec![format!("{} > ?", key)], vec![KinglerValue::Int(after_id)]),
+            None => (Vec::new(), Vec::new()),
+        };
+        let query = format!(
+            "{} ORDER BY {} LIMIT {}",
+            sql::build_select(&database.quote_ident(&Self::table_name::<T>(naming)), &[], &conditions),
+            key,
+            limit
+        );
+        Ok(sqlite.query(&query, params)?)
+    }
+
+    /// Reads every record of a table in batches, handing each batch to `f`
+    /// 
+    /// For jobs going through more records than fit in memory: batches are
+    /// read one at a time with keyset pagination, as in
+    /// [`keyset_page`](Self::keyset_page), so at most `batch_size` records are
+    /// held at once and later batches cost as much as the first. The records
+    /// come in primary key order, which must be an integer. An error returned by
+    /// `f` stops the processing and is returned.
+    /// 
+    /// # Returns
+    /// The number of records processed
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Reading {
+    ///     id: Option<i64>,
+    ///     value: i64,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_process_in_batches.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Reading { id: None, value: 0 }).unwrap();
+    /// db.insert_iter((0..1000).map(|value| Reading { id: None, value })).unwrap();
+    /// 
+    /// let (mut batches, mut total) = (0, 0);
+    /// let processed = db.process_in_batches(100, |batch: Vec<Reading>| {
+    ///     assert_eq!(batch.len(), 100);
+    ///     batches += 1;
+    ///     total += batch.iter().map(|reading| reading.value).sum::<i64>();
+    ///     Ok(())
+    /// }).unwrap();
+    /// assert_eq!(processed, 1000);
+    /// assert_eq!(batches, 10);
+    /// assert_eq!(total, (0..1000).sum::<i64>());
+    /// 
+    /// let result = db.process_in_batches::<Reading, _>(0, |_| Ok(()));
+    /// assert!(matches!(result, Err(KinglerError::InvalidArgument(_))));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::InvalidArgument` if `batch_size` is 0 and
+    /// `KinglerError::MissingPrimaryKey` if a record has no integer primary key
+    /// to continue after.
+    pub fn process_in_batches<T, F>(&self, batch_size: usize, mut f: F) -> Result<usize, KinglerError>
+    where
+        T: Table + DeserializeOwned,
+        F: FnMut(Vec<T>) -> Result<(), KinglerError>,
+    {
+        if batch_size == 0 {
+            return Err(KinglerError::InvalidArgument("batch size must be at least 1".to_string()));
+        }
+        let sqlite = self.connect_read()?;
+        let columns = T::columns();
+        let batch_size = u64::try_from(batch_size).unwrap_or(u64::MAX);
+
+        let mut processed = 0;
+        let mut after = None;
+        loop {
+            let rows = Self::keyset_rows::<T>(&sqlite, &self.naming, after, batch_size)?;
+            let Some(last) = rows.last() else {
+                return Ok(processed);
+            };
+            let last_id = last.get(T::primary_key()).and_then(|key| key.as_i64());
+            after = Some(last_id.ok_or_else(|| KinglerError::MissingPrimaryKey(Self::table_name::<T>(&self.naming)))?);
+
+            processed += rows.len();
+            let batch = rows.into_iter()
+                .map(|row| Self::from_row(&columns, row))
+                .collect::<Result<Vec<T>, KinglerError>>()?;
+            f(batch)?;
+        }
+    }
+
+    /// Counts the distinct values of a column, e.g. for cardinality checks
+    /// 
+    /// Runs `SELECT COUNT(DISTINCT column)`, so nulls aren't counted.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Visit {
+    ///     id: Option<i64>,
+    ///     country: Option<String>,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_count_distinct.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Visit { id: None, country: None }).unwrap();
+    /// for country in [Some("PE"), Some("CL"), Some("PE"), None, Some("PE"), Some("AR")] {
+    ///     db.insert(&Visit { id: None, country: country.map(str::to_string) }).unwrap();
+    /// }
+    /// 
+    /// assert_eq!(db.count_distinct::<Visit>("country").unwrap(), 3);
+    /// assert_eq!(db.count_distinct::<Visit>("id").unwrap(), 6);
+    /// assert!(db.count_distinct::<Visit>("city").is_err());
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::UnknownColumn` if the column isn't a field of `T`.
+    pub fn count_distinct<T: Table>(&self, column: impl TryInto<Ident, Error = KinglerError>) -> Result<i64, KinglerError> {
+        let column = Self::validate_column::<T>(column)?;
+        let sqlite = self.connect_read()?;
+        let database = sqlite.database();
+        let table_name = Self::table_name::<T>(&self.naming);
+        let count = format!("COUNT(DISTINCT {})", column.quoted(&database));
+        let sql = sql::build_select(&database.quote_ident(&table_name), &[count], &[]);
+        let rows = sqlite.query(&sql, Vec::new())?;
+        Ok(rows.first()
+            .and_then(|row| row.values().next())
+            .and_then(|count| count.as_i64())
+            .unwrap_or(0))
+    }
+
+    /// Reads a single column of every record of a table
+    /// 
+    /// Each value is converted to `V` by rusqlite, so `V` can be any type it
+    /// reads, such as `i64`, `String` or `Option<String>` for a nullable column.
+    /// The values come in the table's natural order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_pluck.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// for name in ["Ann", "Bob", "Cid"] {
+    ///     db.insert(&Client { id: None, name: name.to_string() }).unwrap();
+    /// }
+    /// 
+    /// let names: Vec<String> = db.pluck::<Client, String>("name").unwrap();
+    /// assert_eq!(names, vec!["Ann", "Bob", "Cid"]);
+    /// let ids: Vec<i64> = db.pluck::<Client, i64>("id").unwrap();
+    /// assert_eq!(ids, vec![1, 2, 3]);
+    /// assert!(db.pluck::<Client, String>("name; DROP TABLE Client").is_err());
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::UnknownColumn` if `column` isn't a field of `T`,
+    /// and `KinglerError::Sqlite` if a value can't be read as `V`.
+    pub fn pluck<T: Table, V: rusqlite::types::FromSql>(
+        &self,
+        column: impl TryInto<Ident, Error = KinglerError>,
+    ) -> Result<Vec<V>, KinglerError> {
+        let column = Self::validate_column::<T>(column)?;
+        let sqlite = self.connect_read()?;
+        let database = sqlite.database();
+        let table_name = Self::table_name::<T>(&self.naming);
+        let sql = sql::build_select(&database.quote_ident(&table_name), &[column.quoted(&database)], &[]);
+        Ok(sqlite.query_column(&sql, Vec::new())?)
+    }
+
+    /// Serializes every record of a table as a JSON array, e.g. for fixtures
+    /// and backups
+    /// 
+    /// Each element is a record as `T` serializes it, so
+    /// [`load_json`](Self::load_json) can insert the array back.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Debug, PartialEq, Serialize, Deserialize, Table)]
+    /// struct Planet {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     moons: i64,
+    ///     inhabited: bool,
+    /// }
+    /// 
+    /// # let source = std::env::temp_dir().join("kingler_dump_json.db");
+    /// # let target = std::env::temp_dir().join("kingler_dump_json_target.db");
+    /// # let _ = std::fs::remove_file(&source);
+    /// # let _ = std::fs::remove_file(&target);
+    /// # let planet = |name: &str, moons, inhabited| Planet { id: None, name: name.to_string(), moons, inhabited };
+    /// let db = Kingler::new("sqlite".to_string(), source.to_string_lossy().to_string());
+    /// db.create_table(planet("", 0, false)).unwrap();
+    /// db.insert(&planet("Earth", 1, true)).unwrap();
+    /// db.insert(&planet("Mars", 2, false)).unwrap();
+    /// 
+    /// let json = db.dump_json::<Planet>().unwrap();
+    /// assert!(json.starts_with(r#"[{"id":1,"#));
+    /// 
+    /// let copy = Kingler::new("sqlite".to_string(), target.to_string_lossy().to_string());
+    /// copy.create_table(planet("", 0, false)).unwrap();
+    /// assert_eq!(copy.load_json::<Planet>(&json).unwrap(), 2);
+    /// assert_eq!(copy.find_all::<Planet>().unwrap(), db.find_all::<Planet>().unwrap());
+    /// ```
+    pub fn dump_json<T: Table + Serialize + DeserializeOwned>(&self) -> Result<String, KinglerError> {
+        let records = self.find_all::<T>()?;
+        Ok(serde_json::to_string(&records)?)
+    }
+
+    /// Inserts every record of a JSON array as written by
+    /// [`dump_json`](Self::dump_json), returning how many were inserted
+    /// 
+    /// The whole array is parsed before anything is written, then the records
+    /// are inserted in a single transaction like [`insert_iter`](Self::insert_iter).
+    /// Their primary keys are kept, so loading into a table already holding
+    /// those ids fails without inserting anything.
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::Serialization` when the JSON isn't an array of
+    /// records of `T`.
+    pub fn load_json<T: Table + Serialize + DeserializeOwned>(&self, json: &str) -> Result<usize, KinglerError> {
+        let records: Vec<T> = serde_json::from_str(json)?;
+        self.insert_iter(records)
+    }
+
+    /// Writes every record of a table as CSV (`csv` feature)
+    /// 
+    /// The first line is a header naming the table's columns in order. Records
+    /// are read one at a time and go through `T`, so JSON and boolean columns
+    /// are written as they deserialize: nested values as JSON text, booleans as
+    /// `true` or `false` and nulls as empty fields. Fields containing commas,
+    /// quotes or line breaks are quoted.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Contact {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     note: Option<String>,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_export_csv.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Contact { id: None, name: String::new(), note: None }).unwrap();
+    /// # let contact = |name: &str, note: Option<&str>| Contact { id: None, name: name.to_string(), note: note.map(str::to_string) };
+    /// db.insert(&contact("Doe, John", Some("says \"hi\""))).unwrap();
+    /// db.insert(&contact("Jane", None)).unwrap();
+    /// db.insert(&contact("Ann", Some("two\nlines"))).unwrap();
+    /// 
+    /// let mut out = Vec::new();
+    /// db.export_csv::<Contact>(&mut out).unwrap();
+    /// 
+    /// let mut reader = csv::Reader::from_reader(out.as_slice());
+    /// assert_eq!(reader.headers().unwrap(), vec!["id", "name", "note"]);
+    /// let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    /// assert_eq!(rows.len(), 3);
+    /// assert_eq!(rows[0], vec!["1", "Doe, John", "says \"hi\""]);
+    /// assert_eq!(rows[1], vec!["2", "Jane", ""]);
+    /// assert_eq!(rows[2], vec!["3", "Ann", "two\nlines"]);
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::TableNotFound` when the table doesn't exist and
+    /// `KinglerError::Io` when writing fails.
+    #[cfg(feature = "csv")]
+    pub fn export_csv<T: Table + Serialize + DeserializeOwned>(
+        &self,
+        writer: impl std::io::Write,
+    ) -> Result<(), KinglerError> {
+        let table_name = Self::table_name::<T>(&self.naming);
+        let sqlite = self.connect_read()?;
+        let header: Vec<String> = sqlite.describe_table(&table_name)?
+            .into_iter()
+            .map(|column| column.name)
+            .collect();
+        if header.is_empty() {
+            return Err(KinglerError::TableNotFound(table_name));
+        }
+
+        let mut csv = csv::Writer::from_writer(writer);
+        csv.write_record(&header).map_err(|err| KinglerError::Io(err.into()))?;
+
+        let columns = T::columns();
+        let query = sql::build_select(&sqlite.database().quote_ident(&table_name), &[], &[]);
+        sqlite.query_each(&query, Vec::new(), |row| {
+            let record: T = Self::from_row(&columns, row)?;
+            let serde_json::Value::Object(fields) = serde_json::to_value(&record)? else {
+                return Err(KinglerError::NoColumns(table_name.clone()));
+            };
+            let fields = header.iter().map(|name| match fields.get(name) {
+                None | Some(serde_json::Value::Null) => String::new(),
+                Some(serde_json::Value::String(text)) => text.clone(),
+                Some(value) => value.to_string(),
+            });
+            csv.write_record(fields).map_err(|err| KinglerError::Io(err.into()))
+        })?;
+        csv.flush()?;
+        Ok(())
+    }
+
+    /// Inserts the records read from CSV, returning how many were inserted
+    /// (`csv` feature)
+    /// 
+    /// The header row names the column each field goes to, in any order, so a
+    /// file written by [`export_csv`](Self::export_csv) can be read back. Every
+    /// row is parsed into `T` before anything is written, then the records are
+    /// inserted in a single transaction like [`insert_iter`](Self::insert_iter):
+    /// either all of them are stored or none. Empty fields of optional columns
+    /// are read as nulls.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Contact {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     age: i64,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_import_csv.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Contact { id: None, name: String::new(), age: 0 }).unwrap();
+    /// 
+    /// let data = "name,age,id\n\"Doe, John\",30,\nJane,25,\nAnn,41,10\n";
+    /// assert_eq!(db.import_csv::<Contact>(data.as_bytes()).unwrap(), 3);
+    /// 
+    /// let contacts = db.find_all::<Contact>().unwrap();
+    /// assert_eq!(contacts.len(), 3);
+    /// assert_eq!(contacts[0].name, "Doe, John");
+    /// assert_eq!(contacts[2].id, Some(10));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::UnknownColumn` when the header names a column the
+    /// table doesn't have and `KinglerError::Io` when a row can't be read or
+    /// parsed into `T`, inserting nothing in both cases.
+    #[cfg(feature = "csv")]
+    pub fn import_csv<T: Table + Serialize + DeserializeOwned>(
+        &self,
+        reader: impl std::io::Read,
+    ) -> Result<usize, KinglerError> {
+        let mut csv = csv::Reader::from_reader(reader);
+        let header = csv.headers().map_err(|err| KinglerError::Io(err.into()))?;
+        for column in header {
+            Self::validate_column::<T>(column)?;
+        }
+
+        let records = csv.deserialize::<T>()
+            .collect::<Result<Vec<T>, _>>()
+            .map_err(|err| KinglerError::Io(err.into()))?;
+        self.insert_iter(records)
+    }
+
+    /// Internal helper function that indexes the column metadata of the table of
+    /// `T` by name
+    /// 
+    /// Built once per record so looking up each field's column doesn't rescan the
+    /// metadata, which would be quadratic for wide tables.
+    fn column_index<T: Table>() -> HashMap<&'static str, Column> {
+        T::columns().into_iter()
+            .map(|column| (column.name, column))
+            .collect()
+    }
+
+    /// Internal helper function that tells whether a column is generated, and so
+    /// never written
+    fn is_generated(columns: &HashMap<&'static str, Column>, column: &str) -> bool {
+        columns.get(column).is_some_and(|column| column.generated.is_some())
+    }
+
+    /// Internal helper function that converts a serialized field into the value
+    /// bound for its column
+    /// 
+    /// JSON columns store their value as JSON text, even when it is a plain string,
+    /// and 128-bit integer columns store their number as fixed-width text, see
+    /// [`Column::big_integer`].
+    /// With `empty_string_as_null` an empty string is stored as NULL in a text
+    /// column, or in any column of a table without column metadata.
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::IntegerOutOfRange` for a number written to an
+    /// INTEGER column that doesn't fit an `i64`, which SQLite would silently
+    /// turn into an inexact REAL.
+    fn column_value(
+        columns: &HashMap<&'static str, Column>,
+        column: &str,
+        value: &serde_json::Value,
+        empty_string_as_null: bool,
+    ) -> Result<KinglerValue, KinglerError> {
+        let is_empty_string = empty_string_as_null && value.as_str() == Some("");
+        let Some(column) = columns.get(column) else {
+            return Ok(if is_empty_string { KinglerValue::Null } else { KinglerValue::from(value) });
+        };
+        Ok(match value {
+            serde_json::Value::Null => KinglerValue::Null,
+            _ if column.json => KinglerValue::Text(value.to_string()),
+            serde_json::Value::Number(n) if column.big_integer => match Column::encode_big_integer(&n.to_string()) {
+                Some(text) => KinglerValue::Text(text),
+                None => KinglerValue::from(value),
+            },
+            _ if is_empty_string && column.sql_type == SqlType::Text => KinglerValue::Null,
+            serde_json::Value::Number(n) if column.sql_type == SqlType::Integer => match KinglerValue::from(value) {
+                KinglerValue::Text(_) => {
+                    return Err(KinglerError::IntegerOutOfRange {
+                        column: column.name.to_string(),
+                        value: n.to_string(),
+                    });
+                }
+                value => value,
+            },
+            _ => KinglerValue::from(value),
+        })
+    }
+
+    /// Internal helper function that converts a value given for a column, e.g.
+    /// by the bulk updates, into the value bound for it
+    /// 
+    /// Applies the conversions of [`column_value`](Self::column_value) that
+    /// make sense for a value that is already a [`KinglerValue`]:
+    /// `empty_string_as_null` and the encoding of 128-bit integer columns.
+    fn bound_value(
+        columns: &HashMap<&'static str, Column>,
+        column: &str,
+        value: KinglerValue,
+        empty_string_as_null: bool,
+    ) -> KinglerValue {
+        let is_empty_string = empty_string_as_null && matches!(&value, KinglerValue::Text(text) if text.is_empty());
+        match columns.get(column) {
+            None if is_empty_string => KinglerValue::Null,
+            None => value,
+            Some(column) if is_empty_string && column.sql_type == SqlType::Text => KinglerValue::Null,
+            Some(column) => column.stored_value(value),
+        }
+    }
+
+    /// Internal helper function that deserializes a database row into a record of
+    /// type `R`, given the column metadata of its table
+    /// 
+    /// The metadata is fetched once per statement by the callers rather than for
+    /// every row.
+    /// 
+    /// SQLite has no boolean storage class, so BOOLEAN columns are converted back
+    /// from their 0/1 integers before deserializing. Enum columns are checked to
+    /// hold a known variant, while JSON and 128-bit integer columns are parsed back
+    /// from their text.
+    fn from_row<R: DeserializeOwned>(
+        columns: &[Column],
+        mut row: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<R, KinglerError> {
+        for column in columns {
+            let Some(value) = row.get_mut(column.name) else {
+                continue;
+            };
+            if column.sql_type == SqlType::Boolean {
+                if let Some(i) = value.as_i64() {
+                    *value = serde_json::Value::Bool(i != 0);
+                }
+            }
+            if column.json {
+                if let Some(text) = value.as_str() {
+                    *value = serde_json::from_str(text)?;
+                }
+            }
+            if column.big_integer {
+                let number = value.as_str().and_then(|text| {
+                    Column::decode_big_integer(text).unwrap_or_else(|| text.to_string()).parse().ok()
+                });
+                if let Some(number) = number {
+                    *value = serde_json::Value::Number(number);
+                }
+            }
+            if let Some(is_variant) = column.enum_variant {
+                if !is_variant(value) {
+                    return Err(KinglerError::InvalidEnumVariant {
+                        column: column.name.to_string(),
+                        value: value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string()),
+                    });
+                }
+            }
+        }
+        Ok(serde_json::from_value(serde_json::Value::Object(row))?)
+    }
+
+    /// Version of the linked SQLite library as (major, minor, patch)
+    /// 
+    /// Some features depend on it, see [`supports_returning`](Self::supports_returning),
+    /// [`supports_rename_column`](Self::supports_rename_column) and
+    /// [`supports_strict_tables`](Self::supports_strict_tables).
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::Kingler;
+    /// let db = Kingler::new("sqlite".to_string(), "unused.db".to_string());
+    /// let (major, minor, patch) = db.sqlite_version();
+    /// assert_eq!(major, 3);
+    /// assert_eq!(
+    ///     format!("{}.{}.{}", major, minor, patch),
+    ///     rusqlite::Connection::open_in_memory().unwrap()
+    ///         .query_row("SELECT sqlite_version()", [], |row| row.get::<_, String>(0))
+    ///         .unwrap()
+    /// );
+    /// 
+    /// assert_eq!(db.supports_returning(), (major, minor) >= (3, 35));
+    /// assert_eq!(db.supports_rename_column(), (major, minor) >= (3, 25));
+    /// assert_eq!(db.supports_strict_tables(), (major, minor) >= (3, 37));
+    /// // Every feature added before one that is supported is supported too
+    /// assert!(!db.supports_strict_tables() || db.supports_returning());
+    /// assert!(!db.supports_returning() || db.supports_rename_column());
+    /// ```
+    pub fn sqlite_version(&self) -> (u32, u32, u32) {
+        let version = rusqlite::version_number() as u32;
+        (version / 1_000_000, version / 1_000 % 1_000, version % 1_000)
+    }
+
+    /// Whether SQLite supports `RETURNING` clauses (3.35 or later), used by
+    /// [`insert`](Self::insert) and the `_returning` operations when available
+    pub fn supports_returning(&self) -> bool {
+        self.sqlite_version() >= (3, 35, 0)
+    }
+
+    /// Whether SQLite supports `ALTER TABLE ... RENAME COLUMN` (3.25 or later)
+    pub fn supports_rename_column(&self) -> bool {
+        self.sqlite_version() >= (3, 25, 0)
+    }
+
+    /// Whether SQLite supports `STRICT` tables, which reject values of the
+    /// wrong type instead of converting them (3.37 or later)
+    pub fn supports_strict_tables(&self) -> bool {
+        self.sqlite_version() >= (3, 37, 0)
+    }
+
+    /// Reads the schema version stored in the database file (`PRAGMA user_version`)
+    /// 
+    /// 0 unless it was set, e.g. with [`KinglerBuilder::user_version`].
+    pub fn user_version(&self) -> Result<i32, KinglerError> {
+        let sqlite = self.connect_read()?;
+        Ok(sqlite.connection().query_row("PRAGMA user_version", [], |row| row.get(0))?)
+    }
+
+    /// Reads the application id stored in the database file (`PRAGMA application_id`)
+    /// 
+    /// 0 unless it was set, e.g. with [`KinglerBuilder::application_id`].
+    pub fn application_id(&self) -> Result<i32, KinglerError> {
+        let sqlite = self.connect_read()?;
+        Ok(sqlite.connection().query_row("PRAGMA application_id", [], |row| row.get(0))?)
+    }
+
+    /// Checks that the database is reachable, e.g. for readiness probes
+    /// 
+    /// Runs a trivial query against the database, which fails if the file can't
+    /// be opened or isn't a valid database.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::Kingler;
+    /// # let path = std::env::temp_dir().join("kingler_ping.db");
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// assert!(db.ping().is_ok());
+    /// 
+    /// # let corrupt = std::env::temp_dir().join("kingler_ping_corrupt.db");
+    /// std::fs::write(&corrupt, [b'x'; 1024]).unwrap();
+    /// let db = Kingler::new("sqlite".to_string(), corrupt.to_string_lossy().to_string());
+    /// assert!(db.ping().is_err());
+    /// ```
+    pub fn ping(&self) -> Result<(), KinglerError> {
+        let sqlite = self.connect()?;
+        Ok(sqlite.ping()?)
+    }
+
+    /// Runs SQLite's integrity check on the database
+    /// 
+    /// # Returns
+    /// The report of the check, which is `"ok"` for a healthy database
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::Kingler;
+    /// # let path = std::env::temp_dir().join("kingler_integrity_check.db");
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// assert_eq!(db.integrity_check().unwrap(), "ok");
+    /// ```
+    pub fn integrity_check(&self) -> Result<String, KinglerError> {
+        let sqlite = self.connect()?;
+        Ok(sqlite.integrity_check()?)
+    }
+
+    /// Runs `VACUUM` to rebuild the database file and reclaim unused space
+    /// 
+    /// `VACUUM` can't run inside a transaction and needs exclusive access to the
+    /// database, so it fails while a transaction is open, including one started
+    /// with [`Kingler::transaction`].
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_vacuum.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// for i in 0..100 {
+    ///     db.insert(&Client { id: None, name: format!("Client {}", i) }).unwrap();
+    /// }
+    /// 
+    /// db.vacuum().unwrap();
+    /// assert_eq!(db.find_all::<Client>().unwrap().len(), 100);
+    /// 
+    /// db.transaction(|tx| {
+    ///     tx.insert(&Client { id: None, name: "John Doe".to_string() })?;
+    ///     assert!(db.vacuum().is_err());
+    ///     Ok(())
+    /// }).unwrap();
+    /// ```
+    pub fn vacuum(&self) -> Result<(), KinglerError> {
+        let sqlite = self.connect()?;
+        Ok(sqlite.vacuum()?)
+    }
+
+    /// Reads the page statistics of the database, e.g. for monitoring
+    /// 
+    /// The size of the file and the share of it taken by unused pages tell
+    /// when a [`vacuum`](Self::vacuum) is worth running.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_db_stats.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// for index in 0..500 {
+    ///     db.insert(&Client { id: None, name: format!("Client number {}", index) }).unwrap();
+    /// }
+    /// 
+    /// let stats = db.db_stats().unwrap();
+    /// let file_size = std::fs::metadata(&path).unwrap().len();
+    /// assert!(stats.page_count > 1);
+    /// assert_eq!(stats.size_bytes(), file_size);
+    /// 
+    /// db.delete_all::<Client>(false).unwrap();
+    /// let stats = db.db_stats().unwrap();
+    /// assert!(stats.freelist_count > 0);
+    /// db.vacuum().unwrap();
+    /// assert_eq!(db.db_stats().unwrap().freelist_count, 0);
+    /// ```
+    pub fn db_stats(&self) -> Result<sqlite::DbStats, KinglerError> {
+        let sqlite = self.connect_read()?;
+        Ok(sqlite.db_stats()?)
+    }
+
+    /// Runs `ANALYZE` to update the statistics used by the query planner
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::Kingler;
+    /// # let path = std::env::temp_dir().join("kingler_analyze.db");
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.analyze().unwrap();
+    /// ```
+    pub fn analyze(&self) -> Result<(), KinglerError> {
+        let sqlite = self.connect()?;
+        Ok(sqlite.analyze()?)
+    }
+
+    /// Runs the statements of an SQL file, e.g. to bootstrap a schema from
+    /// hand-written DDL
+    /// 
+    /// The statements run in order inside a single transaction: if one fails,
+    /// the changes of the statements before it are rolled back. The file
+    /// shouldn't contain its own `BEGIN` or `COMMIT`.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::Kingler;
+    /// # let dir = std::env::temp_dir();
+    /// # let path = dir.join("kingler_run_sql_file.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let script = dir.join("kingler_run_sql_file.sql");
+    /// std::fs::write(&script, "
+    ///     CREATE TABLE author (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    ///     CREATE TABLE book (id INTEGER PRIMARY KEY, author_id INTEGER REFERENCES author (id), title TEXT);
+    /// ").unwrap();
+    /// 
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.run_sql_file(&script.to_string_lossy()).unwrap();
+    /// assert!(db.find_all_dynamic("author").unwrap().is_empty());
+    /// assert!(db.find_all_dynamic("book").unwrap().is_empty());
+    /// 
+    /// // A failing statement rolls back the whole file
+    /// let broken = dir.join("kingler_run_sql_file_broken.sql");
+    /// std::fs::write(&broken, "CREATE TABLE review (id INTEGER PRIMARY KEY); CREATE TABLE author (id INTEGER);").unwrap();
+    /// assert!(db.run_sql_file(&broken.to_string_lossy()).is_err());
+    /// assert!(db.find_all_dynamic("review").is_err());
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::Io` if the file can't be read and
+    /// `KinglerError::Sqlite` if a statement fails.
+    pub fn run_sql_file(&self, path: &str) -> Result<(), KinglerError> {
+        let script = std::fs::read_to_string(path)?;
+        let sqlite = self.connect()?;
+        sqlite.begin()?;
+        match sqlite.execute_script(&script) {
+            Ok(()) => Ok(sqlite.commit()?),
+            Err(err) => {
+                sqlite.rollback()?;
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Copies the database into the file at `dest_path`, e.g. for snapshots
+    /// 
+    /// Uses SQLite's online backup API, so the database can keep being used
+    /// while it is copied. An existing file at `dest_path` is overwritten.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_backup_to.db");
+    /// # let backup = std::env::temp_dir().join("kingler_backup_to_snapshot.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// # let _ = std::fs::remove_file(&backup);
+    /// # let backup = backup.to_string_lossy().to_string();
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+    /// db.insert(&Client { id: None, name: "Jane Doe".to_string() }).unwrap();
+    /// db.backup_to(&backup).unwrap();
+    /// 
+    /// // Wipe the original database, then bring the snapshot back
+    /// std::fs::remove_file(&path).unwrap();
+    /// assert!(db.find_all::<Client>().is_err());
+    /// 
+    /// db.restore_from(&backup).unwrap();
+    /// assert_eq!(db.find_all::<Client>().unwrap().len(), 2);
+    /// 
+    /// std::fs::remove_file(&backup).unwrap();
+    /// assert!(db.restore_from(&backup).is_err());
+    /// assert_eq!(db.find_all::<Client>().unwrap().len(), 2);
+    /// ```
+    pub fn backup_to(&self, dest_path: &str) -> Result<(), KinglerError> {
+        let sqlite = self.connect()?;
+        Ok(sqlite.backup_to(dest_path)?)
+    }
+
+    /// Replaces the contents of the database with the backup in the file at
+    /// `src_path`
+    /// 
+    /// See [`Kingler::backup_to`].
+    /// 
+    /// # Errors
+    /// Returns `rusqlite::Error::InvalidPath` if there is no file at `src_path`,
+    /// instead of restoring an empty database.
+    pub fn restore_from(&self, src_path: &str) -> Result<(), KinglerError> {
+        if !std::path::Path::new(src_path).is_file() {
+            return Err(KinglerError::Sqlite(rusqlite::Error::InvalidPath(src_path.into())));
+        }
+        let mut sqlite = self.connect()?;
+        Ok(sqlite.restore_from(src_path)?)
+    }
+
+    /// Rewrites numbers stored as TEXT in the numeric columns of a table
+    /// 
+    /// Older versions stringified every value on insert, leaving numbers as text
+    /// in tables whose column types don't convert them back. Such values compare
+    /// as text (`'5' > 9` holds), so this converts every INTEGER and REAL column
+    /// value of `T` that parses as a number, in a single transaction. Text that
+    /// isn't a number is left untouched.
+    /// 
+    /// # Returns
+    /// The number of values that were converted
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{sqlite::Sqlite, Kingler, KinglerValue, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Person {
+    ///     id: Option<i64>,
+    ///     age: i64,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_repair_numeric_columns.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// # let uri = path.to_string_lossy().to_string();
+    /// // A legacy table holding its ages as text
+    /// let legacy = Sqlite::new(uri.clone()).unwrap();
+    /// legacy.create_table("Person".to_string(), vec!["id INTEGER PRIMARY KEY".to_string(), "age".to_string()]).unwrap();
+    /// for age in ["5", "42"] {
+    ///     legacy.insert("Person".to_string(), vec!["age".to_string()], vec![KinglerValue::from(age)]).unwrap();
+    /// }
+    /// 
+    /// let adults = "SELECT id FROM Person WHERE age >= 18";
+    /// assert_eq!(legacy.query(adults, Vec::new()).unwrap().len(), 2);
+    /// 
+    /// let db = Kingler::new("sqlite".to_string(), uri);
+    /// assert_eq!(db.repair_numeric_columns::<Person>().unwrap(), 2);
+    /// assert_eq!(legacy.query(adults, Vec::new()).unwrap().len(), 1);
+    /// 
+    /// let adults = Query::<Person>::new().where_raw("age >= ?", vec![KinglerValue::from(18)]);
+    /// assert_eq!(adults.fetch(&db).unwrap()[0].age, 42);
+    /// ```
+    pub fn repair_numeric_columns<T: Table>(&self) -> Result<usize, KinglerError> {
+        self.transaction(|tx| {
+            let database = tx.sqlite.database();
+            let table_name = database.quote_ident(&Self::table_name::<T>(&tx.naming));
+            let mut repaired = 0;
+
+            for column in T::columns() {
+                if column.sql_type != SqlType::Integer && column.sql_type != SqlType::Real {
+                    continue;
+                }
+                let column_name = database.quote_ident(column.name);
+                let query = format!(
+                    "SELECT rowid AS row_id, {} AS value FROM {} WHERE typeof({}) = 'text'",
+                    column_name, table_name, column_name
+                );
+
+                for row in tx.sqlite.query(&query, Vec::new())? {
+                    let (Some(row_id), Some(text)) = (row["row_id"].as_i64(), row["value"].as_str()) else {
+                        continue;
+                    };
+                    let text = text.trim();
+                    let value = match text.parse::<i64>() {
+                        Ok(i) if column.sql_type == SqlType::Integer => KinglerValue::Int(i),
+                        _ => match text.parse::<f64>() {
+                            Ok(f) if f.is_finite() => KinglerValue::Real(f),
+                            _ => continue,
+                        },
+                    };
+                    repaired += tx.sqlite.update(
+                        table_name.clone(),
+                        vec![column_name.clone()],
+                        vec![value],
+                        "rowid".to_string(),
+                        KinglerValue::Int(row_id),
+                    )?;
+                }
+            }
+            Ok(repaired)
+        })
+    }
+
+    /// Finds the record of a table with the given primary key
+    /// 
+    /// # Returns
+    /// The record, or `None` if no record has that key
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::TableNotFound` if the table hasn't been created, so
+    /// a missing table isn't mistaken for a missing record.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_find_by_id.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// let id = db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+    /// 
+    /// let client: Option<Client> = db.find_by_id(id).unwrap();
+    /// assert_eq!(client.unwrap().name, "John Doe");
+    /// assert!(db.find_by_id::<Client>(42).unwrap().is_none());
+    /// ```
+    /// 
+    /// Tables can use another primary key with `#[column(primary_key)]`:
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Device {
+    ///     #[column(primary_key)]
+    ///     uuid: String,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_find_by_id_uuid.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Device { uuid: String::new(), name: String::new() }).unwrap();
+    /// db.insert(&Device { uuid: "0b6e3c2a".to_string(), name: "Sensor".to_string() }).unwrap();
+    /// 
+    /// assert_eq!(Device::primary_key(), "uuid");
+    /// let device: Option<Device> = db.find_by_id("0b6e3c2a").unwrap();
+    /// assert_eq!(device.unwrap().name, "Sensor");
+    /// ```
+    /// 
+    /// Looking up a record in a table that doesn't exist is an error:
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_find_by_id_missing_table.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// let result = db.find_by_id::<Client>(1);
+    /// assert!(matches!(result, Err(KinglerError::TableNotFound(table)) if table == "Client"));
+    /// 
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// assert!(db.find_by_id::<Client>(1).unwrap().is_none());
+    /// ```
+    pub fn find_by_id<T: Table + DeserializeOwned>(
+        &self,
+        id: impl Into<KinglerValue>,
+    ) -> Result<Option<T>, KinglerError> {
+        let sqlite = self.connect_read()?;
+        Self::find_by_id_with(&sqlite, &self.naming, id.into())
+    }
+
+    /// Internal helper function that finds a record by primary key using the given connection
+    fn find_by_id_with<T: Table + DeserializeOwned>(
+        sqlite: &sqlite::Sqlite,
+        naming: &TableNaming,
+        id: KinglerValue,
+    ) -> Result<Option<T>, KinglerError> {
+        let records = Self::find_where_with(sqlite, naming, &[(T::primary_key(), id)])?;
+        Ok(records.into_iter().next())
+    }
+
+    /// Updates the stored record with the same primary key as `record`
+    /// 
+    /// # Returns
+    /// The number of updated records, 0 if no record has that key
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::MissingPrimaryKey` if the record's primary key is null.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_update.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// let id = db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap().as_i64().unwrap();
+    /// 
+    /// let updated = db.update(&Client { id: Some(id), name: "John Smith".to_string() }).unwrap();
+    /// assert_eq!(updated, 1);
+    /// assert_eq!(db.find_by_id::<Client>(id).unwrap().unwrap().name, "John Smith");
+    /// ```
+    pub fn update<T: Table + Serialize>(&self, record: &T) -> Result<usize, KinglerError> {
+        let sqlite = self.connect()?;
+        Self::update_with(&sqlite, &self.naming, &self.hooks, self.empty_string_as_null, record)
+    }
+
+    /// Internal helper function that updates a record using the given connection
+    fn update_with<T: Table + Serialize>(
+        sqlite: &sqlite::Sqlite,
+        naming: &TableNaming,
+        hooks: &Hooks,
+        empty_string_as_null: bool,
+        record: &T,
+    ) -> Result<usize, KinglerError> {
+        let database = sqlite.database();
+        let table_name = Self::table_name::<T>(naming);
+        let (columns, values, key) = Self::update_values(database, &table_name, empty_string_as_null, record)?;
+        Hooks::fire(&hooks.before_update, &table_name, || serde_json::to_value(record).unwrap_or_default());
+
+        let updated = sqlite.update(
+            database.quote_ident(&table_name),
+            columns,
+            values,
+            database.quote_ident(T::primary_key()),
+            key,
+        )?;
+        Hooks::fire(&hooks.after_update, &table_name, || serde_json::to_value(record).unwrap_or_default());
+        Ok(updated)
+    }
+
+    /// Internal helper function that splits a record into the quoted columns to
+    /// update, their new values and the primary key identifying it
+    fn update_values<T: Table + Serialize>(
+        database: Database,
+        table_name: &str,
+        empty_string_as_null: bool,
+        record: &T,
+    ) -> Result<(Vec<String>, Vec<KinglerValue>, KinglerValue), KinglerError> {
+        let primary_key = T::primary_key();
+        let serde_json::Value::Object(map) = serde_json::to_value(record)? else {
+            return Err(KinglerError::NoColumns(table_name.to_string()));
+        };
+        let key = match map.get(primary_key) {
+            Some(value) if !value.is_null() => KinglerValue::from(value),
+            _ => return Err(KinglerError::MissingPrimaryKey(table_name.to_string())),
+        };
+
+        let table_columns = Self::column_index::<T>();
+        let mut columns: Vec<String> = Vec::new();
+        let mut values: Vec<KinglerValue> = Vec::new();
+        for (column, value) in map.iter() {
+            if column == primary_key || Self::is_generated(&table_columns, column) {
+                continue;
+            }
+            columns.push(database.quote_ident(column));
+            values.push(Self::column_value(&table_columns, column, value, empty_string_as_null)?);
+        }
+        Ok((columns, values, key))
+    }
+
+    /// Updates the stored record with the same primary key as `record` and
+    /// returns it as stored after the update
+    /// 
+    /// The returned record includes the values set by the database, e.g. by
+    /// triggers. SQLite 3.35 and later use a single `UPDATE ... RETURNING`
+    /// statement; older versions update and read the record back within a
+    /// transaction.
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::MissingPrimaryKey` if the record's primary key is
+    /// null and `rusqlite::Error::QueryReturnedNoRows` if no record has that key.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_update_returning.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// let id = db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap().as_i64().unwrap();
+    /// 
+    /// let client = db.update_returning(&Client { id: Some(id), name: "John Smith".to_string() }).unwrap();
+    /// assert_eq!(client.id, Some(id));
+    /// assert_eq!(client.name, "John Smith");
+    /// 
+    /// let missing = db.update_returning(&Client { id: Some(id + 1), name: "Jane Doe".to_string() });
+    /// assert!(matches!(missing, Err(KinglerError::Sqlite(rusqlite::Error::QueryReturnedNoRows))));
+    /// ```
+    pub fn update_returning<T: Table + Serialize + DeserializeOwned>(&self, record: &T) -> Result<T, KinglerError> {
+        let sqlite = self.connect()?;
+        let database = sqlite.database();
+        let table_name = Self::table_name::<T>(&self.naming);
+        let (columns, values, key) = Self::update_values(database, &table_name, self.empty_string_as_null, record)?;
+        if !sqlite.supports_returning() {
+            return self.transaction(|tx| {
+                tx.update(record)?;
+                tx.find_by_id(key)?
+                    .ok_or(KinglerError::Sqlite(rusqlite::Error::QueryReturnedNoRows))
+            });
+        }
+
+        Hooks::fire(&self.hooks.before_update, &table_name, || serde_json::to_value(record).unwrap_or_default());
+        let rows = sqlite.update_returning(
+            database.quote_ident(&table_name),
+            columns,
+            values,
+            database.quote_ident(T::primary_key()),
+            key,
+        )?;
+        Hooks::fire(&self.hooks.after_update, &table_name, || serde_json::to_value(record).unwrap_or_default());
+        match rows.into_iter().next() {
+            Some(row) => Self::from_row(&T::columns(), row),
+            None => Err(KinglerError::Sqlite(rusqlite::Error::QueryReturnedNoRows)),
+        }
+    }
+
+    /// Deletes the record of a table with the given primary key
+    /// 
+    /// # Returns
+    /// The number of deleted records, 0 if no record has that key
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_delete_by_id.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// let id = db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+    /// 
+    /// assert_eq!(db.delete_by_id::<Client>(id.clone()).unwrap(), 1);
+    /// assert!(db.find_by_id::<Client>(id).unwrap().is_none());
+    /// ```
+    pub fn delete_by_id<T: Table>(&self, id: impl Into<KinglerValue>) -> Result<usize, KinglerError> {
+        let sqlite = self.connect()?;
+        Self::delete_by_id_with::<T>(&sqlite, &self.naming, &self.hooks, id.into())
+    }
+
+    /// Deletes the records of a table whose primary key is any of the given ids
+    /// 
+    /// The records are deleted with a single `DELETE ... WHERE id IN (...)`
+    /// statement, each id bound as a parameter. The delete hooks fire once per
+    /// id, as for [`delete_by_id`](Self::delete_by_id), whether or not a record
+    /// has it. An empty slice deletes nothing and runs no statement.
+    /// 
+    /// # Returns
+    /// The number of deleted records, ids without a record not counting
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_delete_by_ids.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// let mut ids = Vec::new();
+    /// for index in 0..5 {
+    ///     match db.insert(&Client { id: None, name: format!("Client {}", index) }).unwrap() {
+    ///         KinglerValue::Int(id) => ids.push(id),
+    ///         other => panic!("unexpected id {:?}", other),
+    ///     }
+    /// }
+    /// 
+    /// assert_eq!(db.delete_by_ids::<Client>(&[ids[0], ids[2], ids[4]]).unwrap(), 3);
+    /// let names: Vec<String> = db.find_all::<Client>().unwrap().into_iter().map(|client| client.name).collect();
+    /// assert_eq!(names, vec!["Client 1", "Client 3"]);
+    /// 
+    /// assert_eq!(db.delete_by_ids::<Client>(&[]).unwrap(), 0);
+    /// assert_eq!(db.delete_by_ids::<Client>(&[ids[0]]).unwrap(), 0);
+    /// ```
+    pub fn delete_by_ids<T: Table>(&self, ids: &[i64]) -> Result<usize, KinglerError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let sqlite = self.connect()?;
+        let database = sqlite.database();
+        let table_name = Self::table_name::<T>(&self.naming);
+        let key = |id: i64| move || serde_json::json!({ T::primary_key(): id });
+        for &id in ids {
+            Hooks::fire(&self.hooks.before_delete, &table_name, key(id));
+        }
+
+        let deleted = sqlite.delete_in(
+            database.quote_ident(&table_name),
+            database.quote_ident(T::primary_key()),
+            ids.iter().map(|&id| KinglerValue::Int(id)).collect(),
+        )?;
+        for &id in ids {
+            Hooks::fire(&self.hooks.after_delete, &table_name, key(id));
+        }
+        Ok(deleted)
+    }
+
+    /// Internal helper function that deletes a record by primary key using the given connection
+    fn delete_by_id_with<T: Table>(
+        sqlite: &sqlite::Sqlite,
+        naming: &TableNaming,
+        hooks: &Hooks,
+        id: KinglerValue,
+    ) -> Result<usize, KinglerError> {
+        let database = sqlite.database();
+        let table_name = Self::table_name::<T>(naming);
+        let key = || serde_json::json!({ T::primary_key(): serde_json::Value::from(&id) });
+        Hooks::fire(&hooks.before_delete, &table_name, key);
+
+        let deleted = sqlite.delete(
+            database.quote_ident(&table_name),
+            database.quote_ident(T::primary_key()),
+            id.clone(),
+        )?;
+        Hooks::fire(&hooks.after_delete, &table_name, key);
+        Ok(deleted)
+    }
+
+    /// Deletes the record of a table with the given primary key and returns it
+    /// 
+    /// The record is returned as it was stored right before being deleted, e.g.
+    /// for audit logs. SQLite 3.35 and later do this in a single `DELETE ...
+    /// RETURNING` statement; older versions read the record and delete it within
+    /// a transaction.
+    /// 
+    /// # Returns
+    /// The deleted record, or `None` if no record has that key
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     active: bool,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_delete_returning.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new(), active: false }).unwrap();
+    /// let id = db.insert(&Client { id: None, name: "John Doe".to_string(), active: true }).unwrap().as_i64().unwrap();
+    /// 
+    /// let deleted = db.delete_returning::<Client>(id).unwrap().unwrap();
+    /// assert_eq!(deleted.id, Some(id));
+    /// assert_eq!(deleted.name, "John Doe");
+    /// assert!(deleted.active);
+    /// assert!(db.find_by_id::<Client>(id).unwrap().is_none());
+    /// 
+    /// assert!(db.delete_returning::<Client>(id).unwrap().is_none());
+    /// ```
+    pub fn delete_returning<T: Table + DeserializeOwned>(
+        &self,
+        id: impl Into<KinglerValue>,
+    ) -> Result<Option<T>, KinglerError> {
+        let id = id.into();
+        let sqlite = self.connect()?;
+        if !sqlite.supports_returning() {
+            return self.transaction(|tx| {
+                let record = tx.find_by_id::<T>(id.clone())?;
+                if record.is_some() {
+                    tx.delete_by_id::<T>(id)?;
+                }
+                Ok(record)
+            });
+        }
+
+        let database = sqlite.database();
+        let table_name = Self::table_name::<T>(&self.naming);
+        let key = || serde_json::json!({ T::primary_key(): serde_json::Value::from(&id) });
+        Hooks::fire(&self.hooks.before_delete, &table_name, key);
+
+        let rows = sqlite.delete_returning(
+            database.quote_ident(&table_name),
+            database.quote_ident(T::primary_key()),
+            id.clone(),
+        )?;
+        let record = rows.into_iter()
+            .next()
+            .map(|row| Self::from_row(&T::columns(), row))
+            .transpose()?;
+        if record.is_some() {
+            Hooks::fire(&self.hooks.after_delete, &table_name, key);
+        }
+        Ok(record)
+    }
+
+    /// Inserts several records in a single transaction
+    /// 
+    /// Either every record is inserted or, if one of them fails, none are.
+    /// 
+    /// # Returns
+    /// The ids of the inserted records, in the order of `records`
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_insert_many.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// 
+    /// let ids = db.insert_many(&[
+    ///     Client { id: None, name: "John Doe".to_string() },
+    ///     Client { id: None, name: "Jane Doe".to_string() },
+    /// ]).unwrap();
+    /// assert_eq!(ids, vec![KinglerValue::Int(1), KinglerValue::Int(2)]);
+    /// ```
+    /// 
+    /// A record failing partway through rolls the whole batch back and its error
+    /// is returned:
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Account {
+    ///     id: Option<i64>,
+    ///     #[column(unique)]
+    ///     email: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_insert_many_rollback.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Account { id: None, email: String::new() }).unwrap();
+    /// 
+    /// let account = |email: &str| Account { id: None, email: email.to_string() };
+    /// let result = db.insert_many(&[
+    ///     account("a@example.com"),
+    ///     account("b@example.com"),
+    ///     account("a@example.com"),
+    ///     account("c@example.com"),
+    ///     account("d@example.com"),
+    /// ]);
+    /// let Err(KinglerError::Sqlite(err)) = result else { panic!("expected a constraint violation") };
+    /// assert_eq!(err.sqlite_error_code(), Some(rusqlite::ErrorCode::ConstraintViolation));
+    /// assert!(db.find_all::<Account>().unwrap().is_empty());
+    /// ```
+    pub fn insert_many<T: Table + Serialize>(&self, records: &[T]) -> Result<Vec<KinglerValue>, KinglerError> {
+        self.transaction(|tx| {
+            records.iter()
+                .map(|record| tx.insert(record))
+                .collect()
+        })
+    }
+
+    /// Inserts several records in a single transaction and returns them as stored
+    /// 
+    /// Each record is read back after being inserted, so the returned records
+    /// include their assigned id and any column defaults.
+    /// 
+    /// # Returns
+    /// The stored records, in the order of `records`
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_insert_many_returning.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// 
+    /// let clients = db.insert_many_returning(&[
+    ///     Client { id: None, name: "John Doe".to_string() },
+    ///     Client { id: None, name: "Jane Doe".to_string() },
+    ///     Client { id: None, name: "Richard Roe".to_string() },
+    /// ]).unwrap();
+    /// let ids: Vec<Option<i64>> = clients.iter().map(|client| client.id).collect();
+    /// assert_eq!(ids, vec![Some(1), Some(2), Some(3)]);
+    /// assert_eq!(clients[2].name, "Richard Roe");
+    /// ```
+    pub fn insert_many_returning<T: Table + Serialize + DeserializeOwned>(
+        &self,
+        records: &[T],
+    ) -> Result<Vec<T>, KinglerError> {
+        self.transaction(|tx| {
+            records.iter()
+                .map(|record| {
+                    let id = tx.insert(record)?;
+                    tx.find_by_id(id)?
+                        .ok_or(KinglerError::Sqlite(rusqlite::Error::QueryReturnedNoRows))
+                })
+                .collect()
+        })
+    }
+
+    /// Inserts the records produced by an iterator in a single transaction
+    /// 
+    /// The records are consumed one at a time and written in multi-row `INSERT`
+    /// statements of up to [`KinglerBuilder::insert_chunk_size`] records, so an
+    /// import read line by line from a file never has to be collected first.
+    /// Either every record is inserted or, if one of them fails, none are. The
+    /// after insert hooks receive each record with the key it was stored under.
+    /// 
+    /// # Returns
+    /// The number of inserted records
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// # use std::sync::{Arc, Mutex};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Item {
+    ///     id: Option<i64>,
+    ///     sku: String,
+    ///     stock: i64,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_insert_iter.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::builder("sqlite".to_string(), path.to_string_lossy().to_string())
+    ///     .insert_chunk_size(64)
+    ///     .build()
+    ///     .unwrap();
+    /// db.create_table(Item { id: None, sku: String::new(), stock: 0 }).unwrap();
+    /// let ids = Arc::new(Mutex::new(Vec::new()));
+    /// let log = ids.clone();
+    /// db.on_after_insert(move |_, record| log.lock().unwrap().push(record["id"].as_i64()));
+    /// 
+    /// let lines = (0..1000).map(|i| format!("SKU-{},{}", i, i % 7));
+    /// let items = lines.map(|line| {
+    ///     let (sku, stock) = line.split_once(',').unwrap();
+    ///     Item { id: None, sku: sku.to_string(), stock: stock.parse().unwrap() }
+    /// });
+    /// assert_eq!(db.insert_iter(items).unwrap(), 1000);
+    /// 
+    /// let stored = db.find_all::<Item>().unwrap();
+    /// assert_eq!(stored.len(), 1000);
+    /// assert_eq!(stored[999].sku, "SKU-999");
+    /// assert_eq!(stored[999].id, Some(1000));
+    /// assert_eq!(*ids.lock().unwrap(), (1..=1000).map(Some).collect::<Vec<_>>());
+    /// ```
+    pub fn insert_iter<T, I>(&self, iter: I) -> Result<usize, KinglerError>
+    where
+        T: Table + Serialize,
+        I: IntoIterator<Item = T>,
+    {
+        self.transaction(|tx| {
+            let database = tx.sqlite.database();
+            let table_name = Self::table_name::<T>(&tx.naming);
+            let quoted_table = database.quote_ident(&table_name);
+            let table_columns = Self::column_index::<T>();
+
+            // Records are batched while they write the same columns
+            let hooks = &tx.hooks;
+            let serialize = |record: &T| serde_json::to_value(record).unwrap_or_default();
+            let mut inserted = 0;
+            let mut batch_columns: Vec<String> = Vec::new();
+            let mut batch_values: Vec<KinglerValue> = Vec::new();
+            let mut batch_records: Vec<serde_json::Value> = Vec::new();
+            let mut batch_rows = 0;
+            let flush = |columns: Vec<String>, values: Vec<KinglerValue>, records: Vec<serde_json::Value>| {
+                if records.is_empty() {
+                    return Ok::<_, KinglerError>(tx.sqlite.insert_rows(quoted_table.clone(), columns, values)?);
+                }
+
+                // The after insert hooks need the key each record was stored under
+                let keys = if tx.sqlite.supports_returning() {
+                    let key_column = match records[0].get(T::primary_key()) {
+                        Some(_) => database.quote_ident(T::primary_key()),
+                        None => "rowid".to_string(),
+                    };
+                    tx.sqlite.insert_rows_returning(quoted_table.clone(), columns, values, key_column)?
+                } else {
+                    values.chunks(columns.len())
+                        .map(|row| tx.sqlite.insert(quoted_table.clone(), columns.clone(), row.to_vec()).map(KinglerValue::Int))
+                        .collect::<Result<_, _>>()?
+                };
+                let rows = keys.len();
+                for (record, key) in records.into_iter().zip(keys) {
+                    let record = match record.get(T::primary_key()) {
+                        Some(serde_json::Value::Null) | None => Self::keyed_record::<T>(record, &key),
+                        Some(_) => record,
+                    };
+                    Hooks::fire(&hooks.after_insert, &table_name, || record);
+                }
+                Ok(rows)
+            };
+            for record in iter {
+                let (columns, values, _) =
+                    Self::insert_values(database, &table_name, &table_columns, tx.empty_string_as_null, &record)?;
+                Hooks::fire(&hooks.before_insert, &table_name, || serialize(&record));
+                if columns.is_empty() {
+                    let rowid = tx.sqlite.insert(quoted_table.clone(), columns, values)?;
+                    Hooks::fire(&hooks.after_insert, &table_name, || {
+                        Self::keyed_record::<T>(serialize(&record), &KinglerValue::Int(rowid))
+                    });
+                    inserted += 1;
+                    continue;
+                }
+
+                let chunk_size = self.insert_chunk_size.min(MAX_VARIABLES / columns.len()).max(1);
+                if batch_rows > 0 && (columns != batch_columns || batch_rows >= chunk_size) {
+                    let values = std::mem::take(&mut batch_values);
+                    let records = std::mem::take(&mut batch_records);
+                    inserted += flush(batch_columns.clone(), values, records)?;
+                    batch_rows = 0;
+                }
+                batch_columns = columns;
+                batch_values.extend(values);
+                if Hooks::any(&hooks.after_insert) {
+                    batch_records.push(serialize(&record));
+                }
+                batch_rows += 1;
+            }
+            if batch_rows > 0 {
+                inserted += flush(batch_columns, batch_values, batch_records)?;
+            }
+            Ok(inserted)
+        })
+    }
+
+    /// Reads every row of a table without a struct to deserialize it into
+    /// 
+    /// Each row maps the column names reported by the statement to typed values,
+    /// which is useful for dynamic views such as admin pages.
+    /// 
+    /// # Arguments
+    /// * `table` - Name of the table to read
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::Serialize;
+    /// #[derive(Serialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     age: i64,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_find_all_dynamic.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new(), age: 0 }).unwrap();
+    /// db.insert(&Client { id: None, name: "John Doe".to_string(), age: 25 }).unwrap();
+    /// 
+    /// let rows = db.find_all_dynamic("Client").unwrap();
+    /// assert_eq!(rows[0]["name"], KinglerValue::Text("John Doe".to_string()));
+    /// assert_eq!(rows[0]["age"], KinglerValue::Int(25));
+    /// ```
+    pub fn find_all_dynamic(&self, table: &str) -> Result<Vec<HashMap<String, KinglerValue>>, KinglerError> {
+        Self::validate_identifier(table)?;
+        let sqlite = self.connect_read()?;
+        let sql = format!("SELECT * FROM {}", sqlite.database().quote_ident(table));
+        Ok(sqlite.query_values(&sql, Vec::new())?)
+    }
+
+    /// Reads only the given columns of every row of a table
+    /// 
+    /// Unlike [`find_all_dynamic`](Self::find_all_dynamic) the other columns
+    /// are never fetched, which keeps exports of a few columns of wide tables
+    /// cheap. Without columns every column is read.
+    /// 
+    /// # Arguments
+    /// * `table` - Name of the table to read
+    /// * `columns` - Names of the columns to read
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::InvalidIdentifier` if the table or one of the
+    /// columns isn't a valid identifier.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::Serialize;
+    /// #[derive(Serialize, Table)]
+    /// struct Employee {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     salary: i64,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_project.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Employee { id: None, name: String::new(), salary: 0 }).unwrap();
+    /// db.insert(&Employee { id: None, name: "John Doe".to_string(), salary: 5000 }).unwrap();
+    /// 
+    /// let rows = db.project("Employee", &["id", "name"]).unwrap();
+    /// assert_eq!(rows[0].len(), 2);
+    /// assert_eq!(rows[0]["id"], KinglerValue::Int(1));
+    /// assert_eq!(rows[0]["name"], KinglerValue::Text("John Doe".to_string()));
+    /// assert!(!rows[0].contains_key("salary"));
+    /// ```
+    pub fn project(&self, table: &str, columns: &[&str]) -> Result<Vec<HashMap<String, KinglerValue>>, KinglerError> {
+        Self::validate_identifier(table)?;
+        for column in columns {
+            Self::validate_identifier(column)?;
+        }
+
+        let sqlite = self.connect_read()?;
+        let database = sqlite.database();
+        let columns: Vec<String> = columns.iter().map(|column| database.quote_ident(column)).collect();
+        let query = sql::build_select(&database.quote_ident(table), &columns, &[]);
+        Ok(sqlite.query_values(&query, Vec::new())?)
+    }
+
+    /// Runs a raw parameterized `SELECT` and deserializes each row into `T` by
+    /// column name
+    /// 
+    /// Suits queries the builders can't express, such as joins and subqueries,
+    /// whose rows still fit a struct: alias the columns (`AS`) to the names of
+    /// its fields. `T` doesn't need to be a table, so no column metadata is
+    /// applied and booleans are read as their 0/1 integers. The statement is run
+    /// as given, so bind values to `?` placeholders instead of formatting them
+    /// into it.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Table)]
+    /// struct Author {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// #[derive(Serialize, Table)]
+    /// struct Book {
+    ///     id: Option<i64>,
+    ///     author_id: i64,
+    ///     title: String,
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq, Deserialize)]
+    /// struct BookListing {
+    ///     title: String,
+    ///     author: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_query_as.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Author { id: None, name: String::new() }).unwrap();
+    /// db.create_table(Book { id: None, author_id: 0, title: String::new() }).unwrap();
+    /// let ursula = db.insert(&Author { id: None, name: "Ursula".to_string() }).unwrap();
+    /// let terry = db.insert(&Author { id: None, name: "Terry".to_string() }).unwrap();
+    /// for (author, title) in [(&ursula, "Earthsea"), (&terry, "Mort"), (&ursula, "The Dispossessed")] {
+    ///     let author_id = author.as_i64().unwrap();
+    ///     db.insert(&Book { id: None, author_id, title: title.to_string() }).unwrap();
+    /// }
+    /// 
+    /// let listings: Vec<BookListing> = db.query_as(
+    ///     "SELECT Book.title AS title, Author.name AS author \
+    ///      FROM Book JOIN Author ON Author.id = Book.author_id \
+    ///      WHERE Author.name = ? ORDER BY Book.title",
+    ///     &[KinglerValue::from("Ursula")],
+    /// ).unwrap();
+    /// assert_eq!(listings, vec![
+    ///     BookListing { title: "Earthsea".to_string(), author: "Ursula".to_string() },
+    ///     BookListing { title: "The Dispossessed".to_string(), author: "Ursula".to_string() },
+    /// ]);
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::Serialization` if a row doesn't deserialize into `T`.
+    pub fn query_as<T: DeserializeOwned>(&self, sql: &str, params: &[KinglerValue]) -> Result<Vec<T>, KinglerError> {
+        let sqlite = self.connect_read()?;
+        sqlite.query(sql, params.to_vec())?
+            .into_iter()
+            .map(|row| Self::from_row(&[], row))
+            .collect()
+    }
+
+    /// Creates a relationship between the tables of two structs
+    /// 
+    /// Both tables are referenced through their primary key and the names of
+    /// the tables and columns created follow the configured table prefix. See
+    /// [`Sqlite::create_relationship`](sqlite::Sqlite::create_relationship) for
+    /// the supported relationship types and what each of them changes.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{sqlite::RelationshipChange, Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Product {
+    ///     id: Option<i64>,
+    ///     title: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_create_relationship.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::builder("sqlite".to_string(), path.to_string_lossy().to_string())
+    ///     .table_prefix("tenant42_")
+    ///     .build()
+    ///     .unwrap();
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// db.create_table(Product { id: None, title: String::new() }).unwrap();
+    /// 
+    /// let change = db.create_relationship::<Client, Product>("MANY_TO_MANY").unwrap();
+    /// assert_eq!(
+    ///     change,
+    ///     RelationshipChange::CreatedJunctionTable("tenant42_client_tenant42_product".to_string())
+    /// );
+    /// ```
+    pub fn create_relationship<A: Table, B: Table>(
+        &self,
+        relation_type: &str,
+    ) -> Result<sqlite::RelationshipChange, KinglerError> {
+        let sqlite = self.connect()?;
+        Ok(sqlite.create_relationship(
+            Self::table_name::<A>(&self.naming),
+            Self::table_name::<B>(&self.naming),
+            A::primary_key().to_string(),
+            B::primary_key().to_string(),
+            relation_type.to_string(),
+        )?)
+    }
+
+    /// Creates a relationship between the tables of two structs whose foreign
+    /// keys take the given action when the record they reference is deleted
+    /// 
+    /// Works like [`create_relationship`](Self::create_relationship), rendering
+    /// the `ON DELETE` clause of the foreign keys it adds. SQLite only enforces
+    /// it on connections with foreign keys enabled, see
+    /// [`KinglerBuilder::foreign_keys`].
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{sqlite::OnDelete, Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Author {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Book {
+    ///     id: Option<i64>,
+    ///     title: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_create_relationship_on_delete.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::builder("sqlite".to_string(), path.to_string_lossy().to_string())
+    ///     .foreign_keys(true)
+    ///     .build()
+    ///     .unwrap();
+    /// db.create_table(Author { id: None, name: String::new() }).unwrap();
+    /// db.create_table(Book { id: None, title: String::new() }).unwrap();
+    /// db.create_relationship_on_delete::<Author, Book>("ONE_TO_MANY", OnDelete::Cascade).unwrap();
+    /// 
+    /// let author = db.insert(&Author { id: None, name: "John Doe".to_string() }).unwrap();
+    /// let other = db.insert(&Author { id: None, name: "Jane Doe".to_string() }).unwrap();
+    /// for (title, writer) in [("Rust", &author), ("SQL", &author), ("Poems", &other)] {
+    ///     let book = db.insert(&Book { id: None, title: title.to_string() }).unwrap();
+    ///     db.with_connection(|conn| {
+    ///         conn.execute("UPDATE Book SET author_ref = ? WHERE id = ?", (writer, &book))
+    ///     }).unwrap();
+    /// }
+    /// 
+    /// db.delete_by_id::<Author>(author).unwrap();
+    /// let books = db.find_all::<Book>().unwrap();
+    /// assert_eq!(books.len(), 1);
+    /// assert_eq!(books[0].title, "Poems");
+    /// ```
+    pub fn create_relationship_on_delete<A: Table, B: Table>(
+        &self,
+        relation_type: &str,
+        on_delete: sqlite::OnDelete,
+    ) -> Result<sqlite::RelationshipChange, KinglerError> {
+        let sqlite = self.connect()?;
+        Ok(sqlite.create_relationship_on_delete(
+            Self::table_name::<A>(&self.naming),
+            Self::table_name::<B>(&self.naming),
+            A::primary_key().to_string(),
+            B::primary_key().to_string(),
+            relation_type.to_string(),
+            Some(on_delete),
+        )?)
+    }
+
+    /// Name of the junction table created by
+    /// [`create_relationship::<A, B>("MANY_TO_MANY")`](Self::create_relationship)
+    /// 
+    /// The name is derived from both table names, including the configured
+    /// prefix, so the junction table can be queried or written directly. Its
+    /// columns are named after the tables with a `_ref` suffix.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{sqlite::RelationshipChange, Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Student {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Course {
+    ///     id: Option<i64>,
+    ///     title: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_junction_table_name.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Student { id: None, name: String::new() }).unwrap();
+    /// db.create_table(Course { id: None, title: String::new() }).unwrap();
+    /// 
+    /// let junction = db.junction_table_name::<Student, Course>();
+    /// assert_eq!(junction, "student_course");
+    /// let change = db.create_relationship::<Student, Course>("MANY_TO_MANY").unwrap();
+    /// assert_eq!(change, RelationshipChange::CreatedJunctionTable(junction.clone()));
+    /// assert!(db.find_all_dynamic(&junction).unwrap().is_empty());
+    /// ```
+    pub fn junction_table_name<A: Table, B: Table>(&self) -> String {
+        sqlite::Sqlite::junction_table_name(
+            &Self::table_name::<A>(&self.naming),
+            &Self::table_name::<B>(&self.naming),
+        )
+    }
+
+    /// Links a record of `A` to a record of `B` through their many-to-many
+    /// junction table
+    /// 
+    /// The junction table must have been created with
+    /// [`create_relationship::<A, B>("MANY_TO_MANY")`](Self::create_relationship).
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Member {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Role {
+    ///     id: Option<i64>,
+    ///     title: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_link.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Member { id: None, name: String::new() }).unwrap();
+    /// db.create_table(Role { id: None, title: String::new() }).unwrap();
+    /// db.create_relationship::<Member, Role>("MANY_TO_MANY").unwrap();
+    /// 
+    /// let member = db.insert(&Member { id: None, name: "John Doe".to_string() }).unwrap();
+    /// let role = db.insert(&Role { id: None, title: "admin".to_string() }).unwrap();
+    /// db.link::<Member, Role>(member.clone(), role.clone()).unwrap();
+    /// assert_eq!(db.find_all_dynamic("member_role").unwrap().len(), 1);
+    /// 
+    /// assert_eq!(db.unlink::<Member, Role>(member, role).unwrap(), 1);
+    /// assert!(db.find_all_dynamic("member_role").unwrap().is_empty());
+    /// ```
+    pub fn link<A: Table, B: Table>(
+        &self,
+        a_id: impl Into<KinglerValue>,
+        b_id: impl Into<KinglerValue>,
+    ) -> Result<(), KinglerError> {
+        let sqlite = self.connect()?;
+        let database = sqlite.database();
+        let (table_a, table_b) = (Self::table_name::<A>(&self.naming), Self::table_name::<B>(&self.naming));
+        sqlite.insert(
+            database.quote_ident(&sqlite::Sqlite::junction_table_name(&table_a, &table_b)),
+            vec![
+                database.quote_ident(&sqlite::Sqlite::reference_column_name(&table_a)),
+                database.quote_ident(&sqlite::Sqlite::reference_column_name(&table_b)),
+            ],
+            vec![a_id.into(), b_id.into()],
+        )?;
+        Ok(())
+    }
+
+    /// Removes the links between a record of `A` and a record of `B` from their
+    /// many-to-many junction table, see [`link`](Self::link)
+    /// 
+    /// # Returns
+    /// The number of links removed
+    pub fn unlink<A: Table, B: Table>(
+        &self,
+        a_id: impl Into<KinglerValue>,
+        b_id: impl Into<KinglerValue>,
+    ) -> Result<usize, KinglerError> {
+        let sqlite = self.connect()?;
+        let database = sqlite.database();
+        let (table_a, table_b) = (Self::table_name::<A>(&self.naming), Self::table_name::<B>(&self.naming));
+        Ok(sqlite.delete_where(
+            database.quote_ident(&sqlite::Sqlite::junction_table_name(&table_a, &table_b)),
+            vec![
+                (database.quote_ident(&sqlite::Sqlite::reference_column_name(&table_a)), a_id.into()),
+                (database.quote_ident(&sqlite::Sqlite::reference_column_name(&table_b)), b_id.into()),
+            ],
+        )?)
+    }
+
+    /// Finds the records of `B` related to the record of `A` with the given
+    /// primary key
+    /// 
+    /// Follows the relationship created by
+    /// [`create_relationship::<A, B>`](Self::create_relationship): a
+    /// many-to-many relationship is joined through its junction table, a
+    /// one-to-many relationship reads the `B` records whose `_ref` column points
+    /// at the `A` record.
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::TableNotFound` with the name of the junction table
+    /// if the tables of `A` and `B` aren't related.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Author {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Book {
+    ///     id: Option<i64>,
+    ///     title: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_find_related.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Author { id: None, name: String::new() }).unwrap();
+    /// db.create_table(Book { id: None, title: String::new() }).unwrap();
+    /// db.create_relationship::<Author, Book>("MANY_TO_MANY").unwrap();
+    /// 
+    /// let author = db.insert(&Author { id: None, name: "John Doe".to_string() }).unwrap();
+    /// let other = db.insert(&Author { id: None, name: "Jane Doe".to_string() }).unwrap();
+    /// for title in ["Rust", "SQL", "Poems"] {
+    ///     let book = db.insert(&Book { id: None, title: title.to_string() }).unwrap();
+    ///     let writer = if title == "Poems" { other.clone() } else { author.clone() };
+    ///     db.link::<Author, Book>(writer, book).unwrap();
+    /// }
+    /// 
+    /// let books: Vec<Book> = db.find_related::<Author, Book>(author).unwrap();
+    /// let titles: Vec<&str> = books.iter().map(|book| book.title.as_str()).collect();
+    /// assert_eq!(titles, vec!["Rust", "SQL"]);
+    /// ```
+    pub fn find_related<A: Table, B: Table + DeserializeOwned>(
+        &self,
+        a_id: impl Into<KinglerValue>,
+    ) -> Result<Vec<B>, KinglerError> {
+        let sqlite = self.connect_read()?;
+        let database = sqlite.database();
+        let (table_a, table_b) = (Self::table_name::<A>(&self.naming), Self::table_name::<B>(&self.naming));
+        let junction = sqlite::Sqlite::junction_table_name(&table_a, &table_b);
+        let a_ref = sqlite::Sqlite::reference_column_name(&table_a);
+
+        let query = if sqlite.table_exists(&junction)? {
+            let b_ref = sqlite::Sqlite::reference_column_name(&table_b);
+            format!(
+                "SELECT {b}.* FROM {b} JOIN {junction} ON {junction}.{b_ref} = {b}.{key} WHERE {junction}.{a_ref} = ?",
+                b = database.quote_ident(&table_b),
+                junction = database.quote_ident(&junction),
+                b_ref = database.quote_ident(&b_ref),
+                key = database.quote_ident(B::primary_key()),
+                a_ref = database.quote_ident(&a_ref),
+            )
+        } else if sqlite.column_exists(&table_b, &a_ref)? {
+            sql::build_select(
+                &database.quote_ident(&table_b),
+                &[],
+                &[format!("{} = ?", database.quote_ident(&a_ref))],
+            )
+        } else {
+            return Err(KinglerError::TableNotFound(junction));
+        };
+
+        let rows = sqlite.query(&query, vec![a_id.into()])?;
+        let columns = B::columns();
+        rows.into_iter()
+            .map(|row| Self::from_row(&columns, row))
+            .collect()
+    }
+
+    /// Renames a table, keeping its records
+    /// 
+    /// Both names are used as given, without the configured table prefix.
+    /// 
+    /// # Errors
+    /// * `KinglerError::InvalidIdentifier` if a name isn't a valid identifier
+    /// * `KinglerError::TableNotFound` if the table doesn't exist
+    /// * `KinglerError::TableExists` if a table named `new_name` already exists
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Customer {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_rename_table.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+    /// 
+    /// db.rename_table("Client", "Customer").unwrap();
+    /// let customers = db.find_all::<Customer>().unwrap();
+    /// assert_eq!(customers[0].name, "John Doe");
+    /// 
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// let result = db.rename_table("Client", "Customer");
+    /// assert!(matches!(result, Err(KinglerError::TableExists(table)) if table == "Customer"));
+    /// ```
+    pub fn rename_table(&self, table_name: &str, new_name: &str) -> Result<(), KinglerError> {
+        Self::validate_identifier(table_name)?;
+        Self::validate_identifier(new_name)?;
+
+        let sqlite = self.connect()?;
+        if !sqlite.table_exists(table_name)? {
+            return Err(KinglerError::TableNotFound(table_name.to_string()));
+        }
+        if sqlite.table_exists(new_name)? {
+            return Err(KinglerError::TableExists(new_name.to_string()));
+        }
+
+        let database = sqlite.database();
+        Ok(sqlite.rename_table(database.quote_ident(table_name), database.quote_ident(new_name))?)
+    }
+
+    /// Sets columns to new values on every record of a table matching all the
+    /// given equality filters
+    /// 
+    /// Every value is bound as a parameter and filters compare like in
+    /// [`Kingler::find_where`]. To update every record use [`Kingler::update_all`].
+    /// 
+    /// # Arguments
+    /// * `set` - Column names paired with their new value
+    /// * `filters` - Column names paired with the value they must be equal to
+    /// 
+    /// # Returns
+    /// The number of updated records
+    /// 
+    /// # Errors
+    /// * `KinglerError::MissingFilter` if `filters` is empty
+    /// * `KinglerError::NoColumns` if `set` is empty
+    /// * `KinglerError::UnknownColumn` if a column is not part of the table
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Ticket {
+    ///     id: Option<i64>,
+    ///     status: String,
+    ///     assignee: Option<String>,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_update_where.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// # let ticket = |status: &str| Ticket { id: None, status: status.to_string(), assignee: None };
+    /// db.create_table(ticket("")).unwrap();
+    /// for status in ["open", "open", "closed"] {
+    ///     db.insert(&ticket(status)).unwrap();
+    /// }
+    /// 
+    /// let updated = db.update_where::<Ticket>(
+    ///     &[("assignee", KinglerValue::from("John"))],
+    ///     &[("status", KinglerValue::from("open"))],
+    /// ).unwrap();
+    /// assert_eq!(updated, 2);
+    /// let johns = db.find_where::<Ticket>(&[("assignee", KinglerValue::from("John"))]).unwrap();
+    /// assert!(johns.iter().all(|ticket| ticket.status == "open"));
+    /// 
+    /// let result = db.update_where::<Ticket>(&[("status", KinglerValue::from("closed"))], &[]);
+    /// assert!(matches!(result, Err(KinglerError::MissingFilter(_))));
+    /// ```
+    pub fn update_where<T: Table>(
+        &self,
+        set: &[(&str, KinglerValue)],
+        filters: &[(&str, KinglerValue)],
+    ) -> Result<usize, KinglerError> {
+        if filters.is_empty() {
+            return Err(KinglerError::MissingFilter(Self::table_name::<T>(&self.naming)));
+        }
+        self.update_where_with::<T>(set, filters)
+    }
+
+    /// Sets columns to new values on every record of a table
+    /// 
+    /// See [`Kingler::update_where`].
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     active: bool,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_update_all.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, active: true }).unwrap();
+    /// db.insert_many(&[Client { id: None, active: true }, Client { id: None, active: true }]).unwrap();
+    /// 
+    /// assert_eq!(db.update_all::<Client>(&[("active", KinglerValue::from(false))]).unwrap(), 2);
+    /// assert!(db.find_all::<Client>().unwrap().iter().all(|client| !client.active));
+    /// ```
+    pub fn update_all<T: Table>(&self, set: &[(&str, KinglerValue)]) -> Result<usize, KinglerError> {
+        self.update_where_with::<T>(set, &[])
+    }
+
+    /// Deletes every record of a table
+    /// 
+    /// With `reset_autoincrement` the AUTOINCREMENT counter is reset as well, so
+    /// the next record gets id 1 again. Tables without AUTOINCREMENT have no
+    /// counter to reset and are only emptied. The delete hooks fire for each
+    /// record, with its primary key as for [`delete_by_id`](Self::delete_by_id).
+    /// 
+    /// # Returns
+    /// The number of deleted records
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Session {
+    ///     id: Option<i64>,
+    ///     token: String,
+    /// }
+    /// 
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Visit {
+    ///     #[column(autoincrement = false)]
+    ///     id: Option<i64>,
+    ///     page: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_delete_all.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// 
+    /// // A plain rowid table has no counter, resetting it is a no-op
+    /// db.create_table(Visit { id: None, page: String::new() }).unwrap();
+    /// db.insert(&Visit { id: None, page: "/".to_string() }).unwrap();
+    /// assert_eq!(db.delete_all::<Visit>(true).unwrap(), 1);
+    /// 
+    /// db.create_table(Session { id: None, token: String::new() }).unwrap();
+    /// db.insert_many(&[
+    ///     Session { id: None, token: "a".to_string() },
+    ///     Session { id: None, token: "b".to_string() },
+    /// ]).unwrap();
+    /// assert_eq!(db.delete_all::<Session>(true).unwrap(), 2);
+    /// let id = db.insert(&Session { id: None, token: "c".to_string() }).unwrap();
+    /// assert_eq!(id, KinglerValue::Int(1));
+    /// ```
+    pub fn delete_all<T: Table>(&self, reset_autoincrement: bool) -> Result<usize, KinglerError> {
+        self.transaction(|tx| {
+            let database = tx.sqlite.database();
+            let table_name = Self::table_name::<T>(&tx.naming);
+            let quoted_table = database.quote_ident(&table_name);
+
+            // The keys are only read when a delete hook needs them
+            let hooks = &tx.hooks;
+            let keys = if Hooks::any(&hooks.before_delete) || Hooks::any(&hooks.after_delete) {
+                let query = format!("SELECT {} FROM {}", database.quote_ident(T::primary_key()), quoted_table);
+                tx.sqlite.query(&query, Vec::new())?
+            } else {
+                Vec::new()
+            };
+            for key in &keys {
+                Hooks::fire(&hooks.before_delete, &table_name, || serde_json::Value::Object(key.clone()));
+            }
+            let deleted = tx.sqlite.delete_where(quoted_table, Vec::new())?;
+            for key in keys {
+                Hooks::fire(&hooks.after_delete, &table_name, || serde_json::Value::Object(key));
+            }
+            if reset_autoincrement {
+                tx.sqlite.reset_sequence(&table_name)?;
+            }
+            Ok(deleted)
+        })
+    }
+
+    /// Updates several records to different values in a single statement
+    /// 
+    /// Each record is given by its primary key with the columns to set, which
+    /// can differ from record to record: the statement sets each column with a
+    /// `CASE` on the key, so the N updates take a single round-trip instead of
+    /// N. Records not given are untouched, as are the columns a record doesn't
+    /// set. A key given more than once has its updates merged, the last value
+    /// of a column winning. The update hooks fire for each key, with an object
+    /// holding the key and the columns set. Values are converted as those of
+    /// [`update`](Self::update), e.g. with `empty_string_as_null`, and every
+    /// value is bound as a parameter, so the records of a call are bounded by
+    /// SQLite's limit on parameters.
+    /// 
+    /// # Returns
+    /// The number of updated records, keys without a record not counting
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// # use std::sync::{Arc, Mutex};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Product {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     price: i64,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_update_many.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Product { id: None, name: String::new(), price: 0 }).unwrap();
+    /// for name in ["Apple", "Pear", "Plum", "Fig"] {
+    ///     db.insert(&Product { id: None, name: name.to_string(), price: 10 }).unwrap();
+    /// }
+    /// 
+    /// let updates = Arc::new(Mutex::new(Vec::new()));
+    /// let log = updates.clone();
+    /// db.on_after_update(move |_, record| log.lock().unwrap().push(record.to_string()));
+    /// 
+    /// let updated = db.update_many::<Product>(&[
+    ///     (1, vec![("price", KinglerValue::from(12))]),
+    ///     (2, vec![("price", KinglerValue::from(8)), ("name", KinglerValue::from("Nashi"))]),
+    ///     (3, vec![("price", KinglerValue::from(15))]),
+    ///     (3, vec![("price", KinglerValue::from(14))]),
+    /// ]).unwrap();
+    /// assert_eq!(updated, 3);
+    /// 
+    /// let products: Vec<(String, i64)> = db.find_all::<Product>().unwrap()
+    ///     .into_iter()
+    ///     .map(|product| (product.name, product.price))
+    ///     .collect();
+    /// assert_eq!(products, vec![
+    ///     ("Apple".to_string(), 12),
+    ///     ("Nashi".to_string(), 8),
+    ///     ("Plum".to_string(), 14),
+    ///     ("Fig".to_string(), 10),
+    /// ]);
+    /// assert_eq!(*updates.lock().unwrap(), vec![
+    ///     r#"{"id":1,"price":12}"#,
+    ///     r#"{"id":2,"name":"Nashi","price":8}"#,
+    ///     r#"{"id":3,"price":14}"#,
+    /// ]);
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::UnknownColumn` if a column isn't a field of `T`.
+    pub fn update_many<T: Table>(&self, updates: &[(i64, Vec<(&str, KinglerValue)>)]) -> Result<usize, KinglerError> {
+        // Updates of the same key are merged, a later value of a column winning
+        let mut merged: Vec<(i64, Vec<(&str, KinglerValue)>)> = Vec::new();
+        for (id, set) in updates {
+            let index = match merged.iter().position(|(key, _)| key == id) {
+                Some(index) => index,
+                None => {
+                    merged.push((*id, Vec::new()));
+                    merged.len() - 1
+                }
+            };
+            for (column, value) in set {
+                Self::validate_column::<T>(*column)?;
+                let merged_set = &mut merged[index].1;
+                match merged_set.iter_mut().find(|(name, _)| name == column) {
+                    Some((_, merged_value)) => *merged_value = value.clone(),
+                    None => merged_set.push((column, value.clone())),
+                }
+            }
+        }
+        merged.retain(|(_, set)| !set.is_empty());
+        if merged.is_empty() {
+            return Ok(0);
+        }
+
+        let sqlite = self.connect()?;
+        let database = sqlite.database();
+        let table_name = Self::table_name::<T>(&self.naming);
+        let table_columns = Self::column_index::<T>();
+        let record = |id: i64, set: &[(&str, KinglerValue)]| {
+            let mut record = serde_json::Map::new();
+            record.insert(T::primary_key().to_string(), serde_json::Value::from(id));
+            for (column, value) in set {
+                record.insert(column.to_string(), serde_json::Value::from(value));
+            }
+            serde_json::Value::Object(record)
+        };
+        for (id, set) in &merged {
+            Hooks::fire(&self.hooks.before_update, &table_name, || record(*id, set));
+        }
+
+        let cases = merged.iter()
+            .map(|(id, set)| {
+                let set = set.iter()
+                    .map(|(column, value)| {
+                        let value = Self::bound_value(&table_columns, column, value.clone(), self.empty_string_as_null);
+                        (database.quote_ident(column), value)
+                    })
+                    .collect();
+                (KinglerValue::Int(*id), set)
+            })
+            .collect();
+        let updated = sqlite.update_cases(
+            database.quote_ident(&table_name),
+            database.quote_ident(T::primary_key()),
+            cases,
+        )?;
+        for (id, set) in &merged {
+            Hooks::fire(&self.hooks.after_update, &table_name, || record(*id, set));
+        }
+        Ok(updated)
+    }
+
+    /// Internal helper function that updates the records matching the filters,
+    /// every record when there are none
+    fn update_where_with<T: Table>(
+        &self,
+        set: &[(&str, KinglerValue)],
+        filters: &[(&str, KinglerValue)],
+    ) -> Result<usize, KinglerError> {
+        let table_name = Self::table_name::<T>(&self.naming);
+        if set.is_empty() {
+            return Err(KinglerError::NoColumns(table_name));
+        }
+        for (column, _) in set.iter().chain(filters) {
+            Self::validate_column::<T>(*column)?;
+        }
+
+        let sqlite = self.connect()?;
+        let database = sqlite.database();
+        let table_columns = Self::column_index::<T>();
+        let (columns, values) = set.iter()
+            .map(|(column, value)| {
+                let value = Self::bound_value(&table_columns, column, value.clone(), self.empty_string_as_null);
+                (database.quote_ident(column), value)
+            })
+            .unzip();
+        let filters = filters.iter()
+            .map(|(column, value)| {
+                (database.quote_ident(column), Self::bound_value(&table_columns, column, value.clone(), false))
+            })
+            .collect();
+        Ok(sqlite.update_where(database.quote_ident(&table_name), columns, values, filters)?)
+    }
+
+    /// Creates an index on columns of the table of `T` if it doesn't already exist
+    /// 
+    /// With a predicate the index is partial, covering only the records matching
+    /// it, e.g. `active = 1`. The predicate is part of the schema so nothing is
+    /// bound: it can't contain `;` or comments, and for tables with column
+    /// metadata every word outside of string literals must be a column of the
+    /// table, an SQL keyword or a common scalar function such as `lower`.
+    /// 
+    /// # Arguments
+    /// * `name` - Name of the index
+    /// * `columns` - Indexed columns, in order
+    /// * `predicate` - Condition limiting the index to the records matching it
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::InvalidIdentifier` if the name isn't a valid
+    /// identifier or the predicate contains disallowed syntax, and
+    /// `KinglerError::UnknownColumn` if a column isn't part of the table.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     email: String,
+    ///     active: bool,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_create_index.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, email: String::new(), active: false }).unwrap();
+    /// db.create_index::<Client>("client_email", &["email"], None).unwrap();
+    /// db.create_index::<Client>("client_active_email", &["email"], Some("active = 1")).unwrap();
+    /// 
+    /// let conn = rusqlite::Connection::open(&path).unwrap();
+    /// let mut stmt = conn.prepare("SELECT name, partial FROM pragma_index_list('Client') ORDER BY name").unwrap();
+    /// let indexes: Vec<(String, bool)> = stmt
+    ///     .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+    ///     .unwrap()
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(indexes, vec![
+    ///     ("client_active_email".to_string(), true),
+    ///     ("client_email".to_string(), false),
+    /// ]);
+    /// 
+    /// let result = db.create_index::<Client>("client_deleted", &["email"], Some("deleted = 0"));
+    /// assert!(matches!(result, Err(KinglerError::UnknownColumn(column)) if column == "deleted"));
+    /// ```
+    pub fn create_index<T: Table>(
+        &self,
+        name: impl TryInto<Ident, Error = KinglerError>,
+        columns: &[impl TryInto<Ident, Error = KinglerError> + Clone],
+        predicate: Option<&str>,
+    ) -> Result<(), KinglerError> {
+        let name = name.try_into()?;
+        let columns = columns.iter()
+            .map(|column| Self::validate_column::<T>(column.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(predicate) = predicate {
+            Self::validate_expression::<T>(predicate)?;
+        }
+
+        let sqlite = self.connect()?;
+        let database = sqlite.database();
+        Ok(sqlite.create_index(
+            name.quoted(&database),
+            database.quote_ident(&Self::table_name::<T>(&self.naming)),
+            columns.iter().map(|column| column.quoted(&database)).collect(),
+            predicate.map(str::to_string),
+        )?)
+    }
+
+    /// Creates an index on an expression over columns of the table of `T`,
+    /// e.g. `lower(email)`, if it doesn't already exist
+    /// 
+    /// Lookups filtering on the same expression can then use the index, such as
+    /// case-insensitive matches. The expression is part of the schema, so it is
+    /// checked like the predicate of [`create_index`](Self::create_index): these
+    /// checks reject anything but columns, keywords, common scalar functions,
+    /// numbers and string literals, but they can't prove the expression sound,
+    /// so it should never come from user input. SQLite itself rejects
+    /// expressions that aren't deterministic, like `random()`.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_create_expression_index.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// db.create_expression_index::<Client>("client_name_lower", "lower(name)").unwrap();
+    /// 
+    /// let conn = rusqlite::Connection::open(&path).unwrap();
+    /// let plan: String = conn.query_row(
+    ///     "EXPLAIN QUERY PLAN SELECT * FROM Client WHERE lower(name) = 'john doe'",
+    ///     [],
+    ///     |row| row.get(3),
+    /// ).unwrap();
+    /// assert!(plan.contains("USING INDEX client_name_lower"), "{}", plan);
+    /// 
+    /// let result = db.create_expression_index::<Client>("client_bad", "lower(name); DROP TABLE Client");
+    /// assert!(matches!(result, Err(KinglerError::InvalidIdentifier(_))));
+    /// let result = db.create_expression_index::<Client>("client_email", "lower(email)");
+    /// assert!(matches!(result, Err(KinglerError::UnknownColumn(column)) if column == "email"));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `KinglerError::InvalidIdentifier` if the name isn't a valid
+    /// identifier or the expression contains disallowed syntax, and
+    /// `KinglerError::UnknownColumn` if a word of it isn't a column of the table,
+    /// a keyword or an allowed function.
+    pub fn create_expression_index<T: Table>(
+        &self,
+        name: impl TryInto<Ident, Error = KinglerError>,
+        expression: &str,
+    ) -> Result<(), KinglerError> {
+        let name = name.try_into()?;
+        if expression.trim().is_empty() {
+            return Err(KinglerError::InvalidIdentifier(expression.to_string()));
+        }
+        Self::validate_expression::<T>(expression)?;
+
+        let sqlite = self.connect()?;
+        let database = sqlite.database();
+        Ok(sqlite.create_index(
+            name.quoted(&database),
+            database.quote_ident(&Self::table_name::<T>(&self.naming)),
+            vec![expression.to_string()],
+            None,
+        )?)
+    }
+
+    /// Internal helper function that checks the identifiers of an expression
+    /// embedded in DDL, which can't be bound as a parameter
+    fn validate_expression<T: Table>(expression: &str) -> Result<(), KinglerError> {
+        const KEYWORDS: &[&str] = &[
+            "AND", "OR", "NOT", "NULL", "IS", "IN", "LIKE", "GLOB", "BETWEEN",
+            "TRUE", "FALSE", "COLLATE", "NOCASE", "BINARY", "RTRIM",
+            "CASE", "WHEN", "THEN", "ELSE", "END", "CAST", "AS",
+            "INTEGER", "REAL", "TEXT", "BLOB", "NUMERIC",
+        ];
+        const FUNCTIONS: &[&str] = &[
+            "lower", "upper", "trim", "ltrim", "rtrim", "length", "substr", "abs",
+            "round", "coalesce", "ifnull", "nullif", "replace", "instr", "json_extract",
+            "date", "time", "datetime", "julianday", "strftime",
+        ];
+        if expression.contains(';') || expression.contains("--") || expression.contains("/*") {
+            return Err(KinglerError::InvalidIdentifier(expression.to_string()));
+        }
+
+        let mut in_string = false;
+        let mut word = String::new();
+        for c in expression.chars().chain(std::iter::once(' ')) {
+            if c == '\'' {
+                in_string = !in_string;
+            } else if !in_string && (c.is_ascii_alphanumeric() || c == '_') {
+                word.push(c);
+                continue;
+            }
+            let is_keyword = KEYWORDS.iter()
+                .chain(FUNCTIONS)
+                .any(|keyword| keyword.eq_ignore_ascii_case(&word));
+            let is_number = word.starts_with(|c: char| c.is_ascii_digit());
+            if !word.is_empty() && !is_keyword && !is_number {
+                Self::validate_column::<T>(&word)?;
+            }
+            word.clear();
+        }
+        if in_string {
+            return Err(KinglerError::InvalidIdentifier(expression.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Runs a closure with direct access to a rusqlite connection
+    /// 
+    /// An escape hatch for what the ORM doesn't cover, such as hand-written SQL
+    /// or statement-level control. The connection is the one used for writes,
+    /// opened with the handle's configuration, and is closed once the closure
+    /// returns, so it can't outlive the call.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     age: i64,
+    /// }
+    /// 
+    /// # let path = std::env::temp_dir().join("kingler_with_connection.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new(), age: 0 }).unwrap();
+    /// db.insert(&Client { id: None, name: "John Doe".to_string(), age: 25 }).unwrap();
+    /// db.insert(&Client { id: None, name: "Jane Doe".to_string(), age: 30 }).unwrap();
+    /// 
+    /// let names = db.with_connection(|conn| {
+    ///     let mut stmt = conn.prepare("SELECT name FROM Client WHERE age > ?1 ORDER BY name")?;
+    ///     let rows = stmt.query_map([20], |row| row.get::<_, String>(0))?;
+    ///     rows.collect::<rusqlite::Result<Vec<String>>>()
+    /// }).unwrap();
+    /// assert_eq!(names, vec!["Jane Doe", "John Doe"]);
+    /// ```
+    pub fn with_connection<F, R>(&self, f: F) -> Result<R, KinglerError>
+    where
+        F: FnOnce(&rusqlite::Connection) -> rusqlite::Result<R>,
+    {
+        let sqlite = self.connect()?;
+        Ok(f(sqlite.connection())?)
+    }
+}
@@ -0,0 +1,814 @@
+use std::marker::PhantomData;
+use std::sync::mpsc;
+use std::thread;
+
+use serde::de::DeserializeOwned;
+
+use crate::sql;
+use crate::sqlite::Sqlite;
+use crate::{Column, Database, Dialect, Ident, Kingler, KinglerError, KinglerValue, Table, TableNaming, Transaction};
+
+/// Rows a [`QueryStream`] reads ahead of the records taken from it
+pub const STREAM_BUFFER: usize = 64;
+
+/// A row read by the thread of a [`QueryStream`]
+type StreamRow = Result<serde_json::Map<String, serde_json::Value>, rusqlite::Error>;
+
+/// Builds a SELECT query against the table of a `Table` struct
+///
+/// Conditions added to the query are combined with AND and every value is
+/// bound as a parameter. Rows are deserialized into `R`, which is the table's
+/// struct itself unless specific columns are projected with [`Query::select`].
+///
+/// # Example
+/// ```rust
+/// # use kingler::{Kingler, Query, Table};
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize, Table)]
+/// struct Client {
+///     id: Option<i64>,
+///     name: String,
+///     age: i64,
+/// }
+///
+/// # let path = std::env::temp_dir().join("kingler_query.db");
+/// # let _ = std::fs::remove_file(&path);
+/// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+/// db.create_table(Client { id: None, name: String::new(), age: 0 }).unwrap();
+/// db.insert(&Client { id: None, name: "John Doe".to_string(), age: 25 }).unwrap();
+/// db.insert(&Client { id: None, name: "Jane Doe".to_string(), age: 30 }).unwrap();
+///
+/// let clients = Query::<Client>::new()
+///     .filter("age", 30)
+///     .fetch(&db)
+///     .unwrap();
+/// assert_eq!(clients.len(), 1);
+/// assert_eq!(clients[0].name, "Jane Doe");
+/// ```
+pub struct Query<T, R = T> {
+    /// Projected columns, `None` selects every column
+    columns: Option<Vec<String>>,
+    /// WHERE conditions combined with AND
+    conditions: Vec<String>,
+    /// Values bound to the placeholders of the conditions, in order
+    params: Vec<KinglerValue>,
+    /// ORDER BY terms, in order
+    order: Vec<String>,
+    /// Maximum number of records returned
+    limit: Option<u64>,
+    /// First error found while building the query, returned when it runs
+    error: Option<KinglerError>,
+    marker: PhantomData<fn() -> (T, R)>,
+}
+
+impl<T: Table> Query<T> {
+    /// Creates a query selecting every record of the table of `T`
+    pub fn new() -> Self {
+        Query {
+            columns: None,
+            conditions: Vec::new(),
+            params: Vec::new(),
+            order: Vec::new(),
+            limit: None,
+            error: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Table> Default for Query<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Table, R: DeserializeOwned> Query<T, R> {
+    /// Keeps only the records whose column equals the given value
+    ///
+    /// A `KinglerValue::Null` value is compared with `IS NULL`, see
+    /// [`Query::filter_null`].
+    pub fn filter(mut self, column: impl TryInto<Ident, Error = KinglerError>, value: impl Into<KinglerValue>) -> Self {
+        if let Some(column) = self.check_column(column) {
+            match Self::column_param(&column, value.into()) {
+                KinglerValue::Null => self.conditions.push(format!("{} IS NULL", Self::quote(&column))),
+                value => {
+                    self.conditions.push(format!("{} = ?", Self::quote(&column)));
+                    self.params.push(value);
+                }
+            }
+        }
+        self
+    }
+
+    /// Keeps only the records whose column matches a `LIKE` pattern
+    ///
+    /// The pattern is bound as a parameter, so `%` and `_` act as wildcards but
+    /// can't inject SQL. SQLite's `LIKE` ignores ASCII case unless
+    /// `PRAGMA case_sensitive_like` is enabled.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_query_filter_like.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// for name in ["John Doe", "Jane Doe", "Richard Roe"] {
+    ///     db.insert(&Client { id: None, name: name.to_string() }).unwrap();
+    /// }
+    ///
+    /// let does = Query::<Client>::new().filter_like("name", "%doe%").fetch(&db).unwrap();
+    /// assert_eq!(does.len(), 2);
+    ///
+    /// let janes = Query::<Client>::new().filter_ilike("name", "JANE%").fetch(&db).unwrap();
+    /// assert_eq!(janes[0].name, "Jane Doe");
+    /// ```
+    pub fn filter_like(mut self, column: impl TryInto<Ident, Error = KinglerError>, pattern: &str) -> Self {
+        if let Some(column) = self.check_column(column) {
+            self.conditions.push(format!("{} LIKE ?", Self::quote(&column)));
+            self.params.push(KinglerValue::from(pattern));
+        }
+        self
+    }
+
+    /// Keeps only the records whose column matches a `LIKE` pattern, ignoring case
+    ///
+    /// Renders `column LIKE ? COLLATE NOCASE`, so an index on the column with
+    /// the `NOCASE` collation can serve it. SQLite's `LIKE` ignores the
+    /// collation once `PRAGMA case_sensitive_like` is enabled, and the match is
+    /// then case-sensitive like [`Query::filter_like`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Shipment {
+    ///     id: Option<i64>,
+    ///     order: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_query_filter_ilike.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Shipment { id: None, order: String::new() }).unwrap();
+    /// for order in ["alpha-1", "Alpha-2", "beta-1"] {
+    ///     db.insert(&Shipment { id: None, order: order.to_string() }).unwrap();
+    /// }
+    ///
+    /// let alphas = Query::<Shipment>::new().filter_ilike("order", "ALP%").fetch(&db).unwrap();
+    /// assert_eq!(alphas.len(), 2);
+    /// ```
+    pub fn filter_ilike(mut self, column: impl TryInto<Ident, Error = KinglerError>, pattern: &str) -> Self {
+        if let Some(column) = self.check_column(column) {
+            self.conditions.push(format!("{} LIKE ? COLLATE NOCASE", Self::quote(&column)));
+            self.params.push(KinglerValue::from(pattern));
+        }
+        self
+    }
+
+    /// Keeps only the records whose JSON column holds a value at the given path
+    ///
+    /// Renders `json_extract(column, path) = value` using SQLite's JSON1 functions,
+    /// where `path` is a JSON path such as `$.status` or `$.tags[0]`.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Details {
+    ///     status: String,
+    ///     priority: i64,
+    /// }
+    ///
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Ticket {
+    ///     id: Option<i64>,
+    ///     #[column(json)]
+    ///     data: Details,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_query_filter_json_eq.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// # let details = |status: &str, priority| Details { status: status.to_string(), priority };
+    /// db.create_table(Ticket { id: None, data: details("", 0) }).unwrap();
+    /// db.insert(&Ticket { id: None, data: details("active", 1) }).unwrap();
+    /// db.insert(&Ticket { id: None, data: details("closed", 2) }).unwrap();
+    ///
+    /// let active = Query::<Ticket>::new()
+    ///     .filter_json_eq("data", "$.status", "active")
+    ///     .fetch(&db)
+    ///     .unwrap();
+    /// assert_eq!(active.len(), 1);
+    /// assert_eq!(active[0].data.priority, 1);
+    /// ```
+    pub fn filter_json_eq(
+        mut self,
+        column: impl TryInto<Ident, Error = KinglerError>,
+        path: &str,
+        value: impl Into<KinglerValue>,
+    ) -> Self {
+        if let Some(column) = self.check_column(column) {
+            self.conditions.push(format!("json_extract({}, ?) = ?", Self::quote(&column)));
+            self.params.push(KinglerValue::from(path));
+            self.params.push(value.into());
+        }
+        self
+    }
+
+    /// Keeps only the records whose column equals one of the given values
+    ///
+    /// Each value is bound as its own parameter. `IN ()` isn't valid SQL, so an
+    /// empty list matches no records.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Ticket {
+    ///     id: Option<i64>,
+    ///     status: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_query_filter_in.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Ticket { id: None, status: String::new() }).unwrap();
+    /// for status in ["open", "closed", "pending"] {
+    ///     db.insert(&Ticket { id: None, status: status.to_string() }).unwrap();
+    /// }
+    ///
+    /// let active = Query::<Ticket>::new()
+    ///     .filter_in("status", vec!["open", "pending"])
+    ///     .fetch(&db)
+    ///     .unwrap();
+    /// assert_eq!(active.len(), 2);
+    ///
+    /// let closed = Query::<Ticket>::new()
+    ///     .filter_not_in("status", vec!["open", "pending"])
+    ///     .fetch(&db)
+    ///     .unwrap();
+    /// assert_eq!(closed[0].status, "closed");
+    ///
+    /// let none = Query::<Ticket>::new().filter_in("status", Vec::<&str>::new()).fetch(&db).unwrap();
+    /// assert!(none.is_empty());
+    ///
+    /// let all = Query::<Ticket>::new().filter_not_in("status", Vec::<&str>::new()).fetch(&db).unwrap();
+    /// assert_eq!(all.len(), 3);
+    /// ```
+    pub fn filter_in<V: Into<KinglerValue>>(self, column: impl TryInto<Ident, Error = KinglerError>, values: Vec<V>) -> Self {
+        self.push_in(column, values, "IN", "0")
+    }
+
+    /// Keeps only the records whose column equals none of the given values
+    ///
+    /// An empty list matches every record.
+    pub fn filter_not_in<V: Into<KinglerValue>>(self, column: impl TryInto<Ident, Error = KinglerError>, values: Vec<V>) -> Self {
+        self.push_in(column, values, "NOT IN", "1")
+    }
+
+    /// Adds an `IN`/`NOT IN` condition, falling back to a constant condition
+    /// when there are no values
+    fn push_in<V: Into<KinglerValue>>(
+        mut self,
+        column: impl TryInto<Ident, Error = KinglerError>,
+        values: Vec<V>,
+        operator: &str,
+        empty_condition: &str,
+    ) -> Self {
+        if let Some(column) = self.check_column(column) {
+            if values.is_empty() {
+                self.conditions.push(empty_condition.to_string());
+            } else {
+                let placeholders = vec!["?"; values.len()].join(", ");
+                self.conditions.push(format!("{} {} ({})", Self::quote(&column), operator, placeholders));
+                self.params.extend(values.into_iter().map(|value| Self::column_param(&column, value.into())));
+            }
+        }
+        self
+    }
+
+    /// Keeps only the records whose column is NULL
+    ///
+    /// SQL comparisons with NULL are never true, so `col = NULL` matches nothing;
+    /// this renders `col IS NULL` instead.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Post {
+    ///     id: Option<i64>,
+    ///     title: String,
+    ///     deleted_at: Option<String>,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_query_filter_null.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Post { id: None, title: String::new(), deleted_at: None }).unwrap();
+    /// db.insert(&Post { id: None, title: "Draft".to_string(), deleted_at: None }).unwrap();
+    /// db.insert(&Post {
+    ///     id: None,
+    ///     title: "Old".to_string(),
+    ///     deleted_at: Some("2024-01-01".to_string()),
+    /// }).unwrap();
+    ///
+    /// let live = Query::<Post>::new().filter_null("deleted_at").fetch(&db).unwrap();
+    /// assert_eq!(live.len(), 1);
+    /// assert_eq!(live[0].title, "Draft");
+    ///
+    /// let deleted = Query::<Post>::new().filter_not_null("deleted_at").fetch(&db).unwrap();
+    /// assert_eq!(deleted.len(), 1);
+    /// assert_eq!(deleted[0].title, "Old");
+    /// ```
+    pub fn filter_null(mut self, column: impl TryInto<Ident, Error = KinglerError>) -> Self {
+        if let Some(column) = self.check_column(column) {
+            self.conditions.push(format!("{} IS NULL", Self::quote(&column)));
+        }
+        self
+    }
+
+    /// Keeps only the records whose column is not NULL
+    pub fn filter_not_null(mut self, column: impl TryInto<Ident, Error = KinglerError>) -> Self {
+        if let Some(column) = self.check_column(column) {
+            self.conditions.push(format!("{} IS NOT NULL", Self::quote(&column)));
+        }
+        self
+    }
+
+    /// Adds a raw SQL condition with its own bound parameters
+    ///
+    /// This is an escape hatch for predicates the builder doesn't model, such as
+    /// SQLite functions. The condition is wrapped in parentheses and combined with
+    /// the other conditions using AND. It is inserted into the query as is, so it
+    /// must never be built from user input; pass values through `params` instead.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Event {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     data: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_query_where_raw.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Event { id: None, name: String::new(), data: String::new() }).unwrap();
+    /// db.insert(&Event { id: None, name: "small".to_string(), data: r#"{"x": 3}"#.to_string() }).unwrap();
+    /// db.insert(&Event { id: None, name: "big".to_string(), data: r#"{"x": 7}"#.to_string() }).unwrap();
+    ///
+    /// let events = Query::<Event>::new()
+    ///     .where_raw("json_extract(data, '$.x') > ?", vec![KinglerValue::Int(5)])
+    ///     .filter("name", "big")
+    ///     .fetch(&db)
+    ///     .unwrap();
+    /// assert_eq!(events.len(), 1);
+    /// assert_eq!(events[0].name, "big");
+    /// ```
+    pub fn where_raw(mut self, condition: &str, params: Vec<KinglerValue>) -> Self {
+        self.conditions.push(format!("({})", condition));
+        self.params.extend(params);
+        self
+    }
+
+    /// Sorts the records by a column in ascending order
+    ///
+    /// Calling it again adds a column to break ties with.
+    pub fn order_by(mut self, column: impl TryInto<Ident, Error = KinglerError>) -> Self {
+        if let Some(column) = self.check_column(column) {
+            self.order.push(format!("{} ASC", Self::quote(&column)));
+        }
+        self
+    }
+
+    /// Sorts the records by a column in descending order
+    ///
+    /// See [`Query::order_by`].
+    pub fn order_by_desc(mut self, column: impl TryInto<Ident, Error = KinglerError>) -> Self {
+        if let Some(column) = self.check_column(column) {
+            self.order.push(format!("{} DESC", Self::quote(&column)));
+        }
+        self
+    }
+
+    /// Sorts the records by a raw SQL expression, such as `RANDOM()` or
+    /// `length(name) DESC`
+    ///
+    /// An escape hatch for orderings [`Query::order_by`] can't express. The
+    /// expression is appended to the other ordering terms as is, without being
+    /// validated, so it must never be built from user input: the caller is
+    /// responsible for it not injecting SQL.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_query_order_by_raw.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// for name in ["Jo", "Anna", "Richard", "Lee"] {
+    ///     db.insert(&Client { id: None, name: name.to_string() }).unwrap();
+    /// }
+    ///
+    /// let shuffled = Query::<Client>::new().order_by_raw("RANDOM()").fetch(&db).unwrap();
+    /// assert_eq!(shuffled.len(), 4);
+    ///
+    /// let longest = Query::<Client>::new()
+    ///     .order_by_raw("length(name) DESC")
+    ///     .order_by("name")
+    ///     .fetch(&db)
+    ///     .unwrap();
+    /// let names: Vec<&str> = longest.iter().map(|client| client.name.as_str()).collect();
+    /// assert_eq!(names, vec!["Richard", "Anna", "Lee", "Jo"]);
+    /// ```
+    pub fn order_by_raw(mut self, expression: &str) -> Self {
+        self.order.push(expression.to_string());
+        self
+    }
+
+    /// Returns at most `limit` records
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Selects only the given columns instead of `SELECT *`
+    ///
+    /// The rows are then deserialized into `S`, which only needs the
+    /// projected fields.
+    ///
+    /// # Errors
+    /// Running the query returns `KinglerError::UnknownColumn` if a column is not
+    /// part of the table.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     age: i64,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct ClientName {
+    ///     id: i64,
+    ///     name: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_query_select.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new(), age: 0 }).unwrap();
+    /// db.insert(&Client { id: None, name: "John Doe".to_string(), age: 25 }).unwrap();
+    ///
+    /// let names: Vec<ClientName> = Query::<Client>::new()
+    ///     .select(&["id", "name"])
+    ///     .fetch(&db)
+    ///     .unwrap();
+    /// assert_eq!(names[0].id, 1);
+    /// assert_eq!(names[0].name, "John Doe");
+    ///
+    /// let unknown = Query::<Client>::new().select::<ClientName>(&["email"]).fetch(&db);
+    /// assert!(unknown.is_err());
+    /// ```
+    pub fn select<S: DeserializeOwned>(
+        mut self,
+        columns: &[impl TryInto<Ident, Error = KinglerError> + Clone],
+    ) -> Query<T, S> {
+        let mut selected = Vec::new();
+        for column in columns {
+            if let Some(column) = self.check_column(column.clone()) {
+                selected.push(Self::quote(&column));
+            }
+        }
+
+        Query {
+            columns: Some(selected),
+            conditions: self.conditions,
+            params: self.params,
+            order: self.order,
+            limit: self.limit,
+            error: self.error,
+            marker: PhantomData,
+        }
+    }
+
+    /// Runs the query and deserializes the matching records
+    pub fn fetch(self, db: &Kingler) -> Result<Vec<R>, KinglerError> {
+        let sqlite = db.connect_read()?;
+        self.fetch_with(&sqlite, &db.naming)
+    }
+
+    /// Runs the query and returns only its first record
+    ///
+    /// Applies `LIMIT 1`, so combined with an ordering it returns the top record.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Player {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     score: i64,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_query_first.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Player { id: None, name: String::new(), score: 0 }).unwrap();
+    /// for (name, score) in [("John", 30), ("Jane", 70), ("Richard", 50)] {
+    ///     db.insert(&Player { id: None, name: name.to_string(), score }).unwrap();
+    /// }
+    ///
+    /// let top = Query::<Player>::new().order_by_desc("score").first(&db).unwrap();
+    /// assert_eq!(top.unwrap().name, "Jane");
+    ///
+    /// let top_two = Query::<Player>::new().order_by_desc("score").limit(2).fetch(&db).unwrap();
+    /// assert_eq!(top_two[1].name, "Richard");
+    ///
+    /// let none = Query::<Player>::new().filter("score", 100).first(&db).unwrap();
+    /// assert!(none.is_none());
+    /// ```
+    pub fn first(mut self, db: &Kingler) -> Result<Option<R>, KinglerError> {
+        self.limit = Some(1);
+        Ok(self.fetch(db)?.into_iter().next())
+    }
+
+    /// Runs the query and returns an iterator deserializing the matching
+    /// records as they are read, without collecting them
+    ///
+    /// Suits result sets too large to hold at once. The statement runs on its
+    /// own connection in a background thread, reading at most
+    /// [`STREAM_BUFFER`] rows ahead of the iterator, and stops once the
+    /// iterator is dropped. The query's errors and connection errors are
+    /// returned right away, while errors met reading the rows are yielded by
+    /// the iterator, which then ends.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Reading {
+    ///     id: Option<i64>,
+    ///     sensor: String,
+    ///     value: f64,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_query_stream.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Reading { id: None, sensor: String::new(), value: 0.0 }).unwrap();
+    /// let readings = (0..500).map(|i| Reading {
+    ///     id: None,
+    ///     sensor: if i % 5 == 0 { "north" } else { "south" }.to_string(),
+    ///     value: i as f64,
+    /// });
+    /// db.insert_iter(readings).unwrap();
+    ///
+    /// let stream = Query::<Reading>::new()
+    ///     .filter("sensor", "north")
+    ///     .order_by_desc("value")
+    ///     .stream(&db)
+    ///     .unwrap();
+    /// let mut count = 0;
+    /// for reading in stream {
+    ///     let reading = reading.unwrap();
+    ///     assert_eq!(reading.sensor, "north");
+    ///     count += 1;
+    /// }
+    /// assert_eq!(count, 100);
+    ///
+    /// // Only the records taken are read
+    /// let top: Vec<Reading> = Query::<Reading>::new()
+    ///     .order_by_desc("value")
+    ///     .stream(&db)
+    ///     .unwrap()
+    ///     .take(3)
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(top[2].value, 497.0);
+    /// ```
+    pub fn stream(self, db: &Kingler) -> Result<QueryStream<R>, KinglerError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+
+        let sqlite = db.connect_read()?;
+        let sql = self.sql(&db.naming);
+        let params = self.params;
+        let (sender, receiver) = mpsc::sync_channel(STREAM_BUFFER);
+        // The statement borrows its connection, so both live in the thread
+        thread::spawn(move || {
+            let result = sqlite.query_each(&sql, params, |row| {
+                sender.send(Ok(row)).map_err(|_| StreamError::Closed)
+            });
+            if let Err(StreamError::Sqlite(err)) = result {
+                let _ = sender.send(Err(err));
+            }
+        });
+
+        Ok(QueryStream {
+            receiver,
+            columns: T::columns(),
+            marker: PhantomData,
+        })
+    }
+
+    /// Counts the records matching the query's filters
+    ///
+    /// Runs `SELECT COUNT(*)` with the same `WHERE` clause as [`Query::fetch`],
+    /// ignoring the ordering, limit and selected columns, e.g. for the total of
+    /// a paginated listing.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     age: i64,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_query_count.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new(), age: 0 }).unwrap();
+    /// for (name, age) in [("John", 25), ("Jane", 30), ("Richard", 25)] {
+    ///     db.insert(&Client { id: None, name: name.to_string(), age }).unwrap();
+    /// }
+    ///
+    /// let count = Query::<Client>::new().filter("age", 25).count(&db).unwrap();
+    /// let clients = Query::<Client>::new().filter("age", 25).fetch(&db).unwrap();
+    /// assert_eq!(count, 2);
+    /// assert_eq!(count as usize, clients.len());
+    /// ```
+    pub fn count(self, db: &Kingler) -> Result<i64, KinglerError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+
+        let sqlite = db.connect_read()?;
+        let table_name = sqlite.database().quote_ident(&Kingler::table_name::<T>(&db.naming));
+        let sql = sql::build_select(&table_name, &["COUNT(*)".to_string()], &self.conditions);
+        let rows = sqlite.query(&sql, self.params)?;
+        Ok(rows.first()
+            .and_then(|row| row.values().next())
+            .and_then(|count| count.as_i64())
+            .unwrap_or(0))
+    }
+
+    /// Runs the query inside a transaction and deserializes the matching records
+    ///
+    /// The query sees the transaction's uncommitted writes.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_query_fetch_in.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    ///
+    /// db.transaction(|tx| {
+    ///     tx.insert(&Client { id: None, name: "John Doe".to_string() })?;
+    ///     let clients = Query::<Client>::new().filter("name", "John Doe").fetch_in(tx)?;
+    ///     assert_eq!(clients.len(), 1);
+    ///     Ok(())
+    /// }).unwrap();
+    /// ```
+    pub fn fetch_in(self, tx: &Transaction) -> Result<Vec<R>, KinglerError> {
+        self.fetch_with(&tx.sqlite, &tx.naming)
+    }
+
+    /// Runs the query using the given connection
+    fn fetch_with(self, sqlite: &Sqlite, naming: &TableNaming) -> Result<Vec<R>, KinglerError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+
+        let sql = self.sql(naming);
+        let rows = sqlite.query(&sql, self.params)?;
+        let columns = T::columns();
+        rows.into_iter()
+            .map(|row| Kingler::from_row(&columns, row))
+            .collect()
+    }
+
+    /// Renders the SELECT statement of the query
+    fn sql(&self, naming: &TableNaming) -> String {
+        let columns = self.columns.as_deref().unwrap_or_default();
+        let table_name = Database::Sqlite.quote_ident(&Kingler::table_name::<T>(naming));
+        let mut sql = sql::build_select(&table_name, columns, &self.conditions);
+        if !self.order.is_empty() {
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&self.order.join(", "));
+        }
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        sql
+    }
+
+    /// Quotes a column name
+    ///
+    /// Queries only run on SQLite connections, so names are quoted for SQLite.
+    fn quote(column: &Ident) -> String {
+        column.quoted(&Database::Sqlite)
+    }
+
+    /// Converts a value compared with a column into the value stored for it,
+    /// encoding integers compared with a 128-bit integer column
+    fn column_param(column: &Ident, value: KinglerValue) -> KinglerValue {
+        match T::columns().iter().find(|c| c.name == column.as_str()) {
+            Some(column) => column.stored_value(value),
+            None => value,
+        }
+    }
+
+    /// Validates a column name, recording the error to return when the query
+    /// runs
+    fn check_column(&mut self, column: impl TryInto<Ident, Error = KinglerError>) -> Option<Ident> {
+        if self.error.is_some() {
+            return None;
+        }
+        match Kingler::validate_column::<T>(column) {
+            Ok(column) => Some(column),
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
+/// Iterator over the records of a query, read as they are needed
+///
+/// Returned by [`Query::stream`].
+pub struct QueryStream<R> {
+    /// Rows sent by the thread running the statement
+    receiver: mpsc::Receiver<StreamRow>,
+    /// Column metadata of the queried table
+    columns: Vec<Column>,
+    marker: PhantomData<fn() -> R>,
+}
+
+impl<R: DeserializeOwned> Iterator for QueryStream<R> {
+    type Item = Result<R, KinglerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = self.receiver.recv().ok()?;
+        Some(row.map_err(KinglerError::from).and_then(|row| Kingler::from_row(&self.columns, row)))
+    }
+}
+
+/// Why the thread of a [`QueryStream`] stopped before the last row
+enum StreamError {
+    /// The stream was dropped
+    Closed,
+    /// Reading a row failed
+    Sqlite(rusqlite::Error),
+}
+
+impl From<rusqlite::Error> for StreamError {
+    fn from(err: rusqlite::Error) -> Self {
+        StreamError::Sqlite(err)
+    }
+}
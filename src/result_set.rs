@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+
+use crate::{Kingler, KinglerError, KinglerValue};
+
+/// Rows returned by a raw query, as typed values under the column names the
+/// statement reports
+///
+/// A middle ground between JSON rows and structs: values keep their SQLite
+/// storage class, can be looked up by column and converted to structs once
+/// their shape is known. Returned by [`Kingler::query_raw`].
+///
+/// # Example
+/// ```rust
+/// # use kingler::{Kingler, KinglerValue, Table};
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize, Table)]
+/// struct Client {
+///     id: Option<i64>,
+///     name: String,
+///     age: i64,
+/// }
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct AgeGroup {
+///     age: i64,
+///     clients: i64,
+/// }
+///
+/// # let path = std::env::temp_dir().join("kingler_result_set.db");
+/// # let _ = std::fs::remove_file(&path);
+/// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+/// db.create_table(Client { id: None, name: String::new(), age: 0 }).unwrap();
+/// for (name, age) in [("John", 25), ("Jane", 30), ("Richard", 25)] {
+///     db.insert(&Client { id: None, name: name.to_string(), age }).unwrap();
+/// }
+///
+/// let sql = "SELECT age, COUNT(*) AS clients FROM Client GROUP BY age ORDER BY age";
+/// let result = db.query_raw(sql, &[]).unwrap();
+/// assert_eq!(result.columns(), ["age", "clients"]);
+/// assert_eq!(result.len(), 2);
+/// let clients = result.column_index("clients").unwrap();
+/// assert_eq!(result.rows()[0][clients], KinglerValue::Int(2));
+/// assert_eq!(result.column_index("name"), None);
+///
+/// let maps = result.clone().into_maps();
+/// assert_eq!(maps[1]["age"], KinglerValue::Int(30));
+/// assert_eq!(maps[1]["clients"], KinglerValue::Int(1));
+///
+/// let groups: Vec<AgeGroup> = result.into_structs().unwrap();
+/// assert_eq!(groups, vec![AgeGroup { age: 25, clients: 2 }, AgeGroup { age: 30, clients: 1 }]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ResultSet {
+    columns: Vec<String>,
+    rows: Vec<Vec<KinglerValue>>,
+}
+
+impl ResultSet {
+    /// Creates a result set from column names and rows holding one value per
+    /// column, in the same order
+    pub fn new(columns: Vec<String>, rows: Vec<Vec<KinglerValue>>) -> Self {
+        ResultSet { columns, rows }
+    }
+
+    /// Names of the columns, in the order of the values of each row
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// The rows, each holding one value per column
+    pub fn rows(&self) -> &[Vec<KinglerValue>] {
+        &self.rows
+    }
+
+    /// Position of a column in the rows, `None` if the result has no such column
+    ///
+    /// The first column of that name when several share it, e.g. the key
+    /// columns of both tables of a join.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column == name)
+    }
+
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether the result has no rows
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Converts each row into a map from column names to values
+    ///
+    /// When several columns share a name the last one is kept.
+    pub fn into_maps(self) -> Vec<HashMap<String, KinglerValue>> {
+        let columns = self.columns;
+        self.rows.into_iter()
+            .map(|row| columns.iter().cloned().zip(row).collect())
+            .collect()
+    }
+
+    /// Deserializes each row into `T` by column name
+    ///
+    /// As for [`Kingler::query_as`], no column metadata is applied, so booleans
+    /// are read as their 0/1 integers and blobs as arrays of bytes.
+    ///
+    /// # Errors
+    /// Returns `KinglerError::Serialization` if a row doesn't deserialize into `T`.
+    pub fn into_structs<T: DeserializeOwned>(self) -> Result<Vec<T>, KinglerError> {
+        let columns = self.columns;
+        self.rows.into_iter()
+            .map(|row| {
+                let row = columns.iter()
+                    .zip(&row)
+                    .map(|(column, value)| (column.clone(), serde_json::Value::from(value)))
+                    .collect();
+                Kingler::from_row(&[], row)
+            })
+            .collect()
+    }
+}
+
+impl Kingler {
+    /// Runs a raw parameterized statement and returns its rows as a
+    /// [`ResultSet`]
+    ///
+    /// The statement is run as given, so bind values to `?` placeholders
+    /// instead of formatting them into it. See [`query_as`](Self::query_as)
+    /// to deserialize the rows right away.
+    pub fn query_raw(&self, sql: &str, params: &[KinglerValue]) -> Result<ResultSet, KinglerError> {
+        let sqlite = self.connect_read()?;
+        Ok(sqlite.query_result_set(sql, params.to_vec())?)
+    }
+}
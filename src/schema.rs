@@ -0,0 +1,304 @@
+use crate::{sql, Database, Dialect, Kingler, KinglerError, Table};
+
+/// Offset basis and prime of the 64-bit FNV-1a hash
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// A change to the database schema needed for it to match a struct, as planned
+/// by [`Kingler::plan_schema`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaChange {
+    /// The table doesn't exist and is created
+    CreateTable {
+        /// Name of the table, prefix included
+        table: String,
+        /// Definitions of its columns and table constraints, as rendered in
+        /// `CREATE TABLE`
+        columns: Vec<String>,
+        /// Whether the table is created `WITHOUT ROWID`
+        without_rowid: bool,
+    },
+    /// A field of the struct has no column in the table yet
+    AddColumn {
+        /// Name of the table, prefix included
+        table: String,
+        /// Name of the missing column
+        column: String,
+        /// Definition of the column without its name (e.g. "TEXT UNIQUE")
+        definition: String,
+    },
+}
+
+impl SchemaChange {
+    /// Renders the statement making the change, quoting names for the dialect
+    pub fn sql(&self, dialect: &dyn Dialect) -> String {
+        match self {
+            SchemaChange::CreateTable { table, columns, without_rowid } => {
+                let sql = sql::build_create_table(&dialect.quote_ident(table), columns);
+                if *without_rowid {
+                    format!("{} WITHOUT ROWID", sql)
+                } else {
+                    sql
+                }
+            }
+            SchemaChange::AddColumn { table, column, definition } => format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                dialect.quote_ident(table),
+                dialect.quote_ident(column),
+                definition
+            ),
+        }
+    }
+}
+
+impl Kingler {
+    /// Lists the changes the database schema needs to match the table of `T`,
+    /// without making them
+    ///
+    /// A missing table is planned as a [`SchemaChange::CreateTable`] with the
+    /// columns [`create_table`](Self::create_table) would create, and each
+    /// field of an existing table without a column as a
+    /// [`SchemaChange::AddColumn`]. Columns are compared by name only, so a
+    /// changed type or constraint isn't reported, nor is a column the struct no
+    /// longer has. The plan is built from the column metadata generated by
+    /// `#[derive(Table)]`, so a manual `Table` implementation without it
+    /// never plans new columns, and planning its missing table fails with
+    /// `KinglerError::NoColumns`. Comparing the rendered statements, see
+    /// [`SchemaChange::sql`], lets CI catch schema changes.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{schema::SchemaChange, Database, Kingler, KinglerError, Table};
+    /// # use serde::Serialize;
+    /// mod v1 {
+    ///     # use kingler::Table;
+    ///     # use serde::Serialize;
+    ///     #[derive(Serialize, Table)]
+    ///     pub struct Client {
+    ///         pub id: Option<i64>,
+    ///         pub name: String,
+    ///     }
+    /// }
+    ///
+    /// #[derive(Serialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     email: Option<String>,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_plan_schema.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// let plan = db.plan_schema::<Client>().unwrap();
+    /// assert!(matches!(&plan[..], [SchemaChange::CreateTable { table, .. }] if table == "Client"));
+    ///
+    /// db.create_table(v1::Client { id: None, name: String::new() }).unwrap();
+    /// let plan = db.plan_schema::<Client>().unwrap();
+    /// assert_eq!(plan, vec![SchemaChange::AddColumn {
+    ///     table: "Client".to_string(),
+    ///     column: "email".to_string(),
+    ///     definition: "TEXT".to_string(),
+    /// }]);
+    /// assert_eq!(plan[0].sql(&Database::Sqlite), r#"ALTER TABLE "Client" ADD COLUMN "email" TEXT"#);
+    ///
+    /// // Nothing is changed
+    /// assert_eq!(db.plan_schema::<Client>().unwrap().len(), 1);
+    /// assert!(db.plan_schema::<v1::Client>().unwrap().is_empty());
+    ///
+    /// struct Manual;
+    /// impl Table for Manual {
+    ///     fn table_name() -> &'static str { "Manual" }
+    ///     fn to_columns(&self) -> Vec<String> { Vec::new() }
+    /// }
+    /// let result = db.plan_schema::<Manual>();
+    /// assert!(matches!(result, Err(KinglerError::NoColumns(table)) if table == "Manual"));
+    /// ```
+    pub fn plan_schema<T: Table>(&self) -> Result<Vec<SchemaChange>, KinglerError> {
+        let table_name = Self::table_name::<T>(&self.naming);
+        let sqlite = self.connect_read()?;
+        let database = sqlite.database();
+        let existing = sqlite.describe_table(&table_name)?;
+        let columns = T::columns();
+
+        if existing.is_empty() {
+            if columns.is_empty() {
+                return Err(KinglerError::NoColumns(table_name));
+            }
+            return Ok(vec![SchemaChange::CreateTable {
+                table: table_name,
+                columns: Self::column_definitions::<T>(database, Self::metadata_columns::<T>(database)),
+                without_rowid: T::without_rowid(),
+            }]);
+        }
+
+        Ok(columns.iter()
+            .filter(|column| !existing.iter().any(|existing| existing.name.eq_ignore_ascii_case(column.name)))
+            .map(|column| SchemaChange::AddColumn {
+                table: table_name.clone(),
+                column: column.name.to_string(),
+                definition: column.definition_for(database),
+            })
+            .collect())
+    }
+
+    /// Number of columns of the table of `T`, as described by the column
+    /// metadata generated by `#[derive(Table)]`
+    ///
+    /// 0 for a manual `Table` implementation without metadata.
+    pub fn column_count<T: Table>(&self) -> usize {
+        T::columns().len()
+    }
+
+    /// Hashes the definition of the table of `T`, e.g. as a cache key or to
+    /// tell deployments whose schemas differ apart
+    ///
+    /// The hash covers the table name, prefix included, and the column
+    /// definitions and constraints [`create_table`](Self::create_table)
+    /// renders for SQLite, so any change to a column's name, type or constraints
+    /// changes it. It is computed with FNV-1a rather than the randomly seeded
+    /// hasher of the standard library, so it is the same across runs, builds
+    /// and machines. Only the column metadata generated by `#[derive(Table)]`
+    /// is hashed and the database isn't read.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::Serialize;
+    /// mod v1 {
+    ///     # use kingler::Table;
+    ///     # use serde::Serialize;
+    ///     #[derive(Serialize, Table)]
+    ///     pub struct Client {
+    ///         pub id: Option<i64>,
+    ///         pub name: String,
+    ///     }
+    /// }
+    ///
+    /// #[derive(Serialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     email: Option<String>,
+    /// }
+    ///
+    /// let db = Kingler::new("sqlite".to_string(), "unused.db".to_string());
+    /// assert_eq!(db.column_count::<v1::Client>(), 2);
+    /// assert_eq!(db.column_count::<Client>(), 3);
+    ///
+    /// // The same in every run
+    /// assert_eq!(db.schema_hash::<v1::Client>(), 0x7f32_c2ee_cae5_6df2);
+    /// assert_eq!(db.schema_hash::<v1::Client>(), db.schema_hash::<v1::Client>());
+    /// assert_ne!(db.schema_hash::<Client>(), db.schema_hash::<v1::Client>());
+    /// ```
+    pub fn schema_hash<T: Table>(&self) -> u64 {
+        let database = Database::Sqlite;
+        let table_name = Self::table_name::<T>(&self.naming);
+        let mut parts = vec![table_name];
+        parts.extend(Self::column_definitions::<T>(database, Self::metadata_columns::<T>(database)));
+        if T::without_rowid() {
+            parts.push("WITHOUT ROWID".to_string());
+        }
+
+        // Each part ends with a NUL byte so moving text between parts changes
+        // the hash
+        parts.iter()
+            .flat_map(|part| part.bytes().chain([0]))
+            .fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME))
+    }
+
+    /// Checks that the columns of the table of `T` match its fields
+    ///
+    /// Compares the column names of the live table with those of the column
+    /// metadata generated by `#[derive(Table)]`, ignoring case, so a struct
+    /// changed without migrating the database is caught at startup. Types and
+    /// constraints aren't compared. See [`assert_schema`](Self::assert_schema)
+    /// for the differences.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::Serialize;
+    /// mod v1 {
+    ///     # use kingler::Table;
+    ///     # use serde::Serialize;
+    ///     #[derive(Serialize, Table)]
+    ///     pub struct Client {
+    ///         pub id: Option<i64>,
+    ///         pub name: String,
+    ///         pub phone: String,
+    ///     }
+    /// }
+    ///
+    /// #[derive(Serialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     email: Option<String>,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_verify_schema.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(v1::Client { id: None, name: String::new(), phone: String::new() }).unwrap();
+    /// assert!(db.verify_schema::<v1::Client>().unwrap());
+    /// assert!(db.assert_schema::<v1::Client>().is_ok());
+    ///
+    /// assert!(!db.verify_schema::<Client>().unwrap());
+    /// match db.assert_schema::<Client>() {
+    ///     Err(KinglerError::SchemaMismatch { missing, extra }) => {
+    ///         assert_eq!(missing, vec!["email"]);
+    ///         assert_eq!(extra, vec!["phone"]);
+    ///     }
+    ///     other => panic!("unexpected result {:?}", other),
+    /// }
+    /// ```
+    pub fn verify_schema<T: Table>(&self) -> Result<bool, KinglerError> {
+        match self.assert_schema::<T>() {
+            Ok(()) => Ok(true),
+            Err(KinglerError::SchemaMismatch { .. }) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Checks that the columns of the table of `T` match its fields, failing
+    /// with the differences when they don't
+    ///
+    /// Compares names as [`verify_schema`](Self::verify_schema) does. A missing
+    /// table reports every field as missing. A manual `Table` implementation
+    /// without column metadata has no fields to compare, so only its table
+    /// existing is checked.
+    ///
+    /// # Errors
+    /// Returns `KinglerError::SchemaMismatch` listing the fields without a
+    /// column and the columns without a field, in the order of the struct and
+    /// of the table.
+    /// Returns `KinglerError::TableNotFound` if a table without column
+    /// metadata doesn't exist.
+    pub fn assert_schema<T: Table>(&self) -> Result<(), KinglerError> {
+        let table_name = Self::table_name::<T>(&self.naming);
+        let sqlite = self.connect_read()?;
+        let existing = sqlite.describe_table(&table_name)?;
+        let columns = T::columns();
+        if columns.is_empty() {
+            if existing.is_empty() {
+                return Err(KinglerError::TableNotFound(table_name));
+            }
+            return Ok(());
+        }
+
+        let missing: Vec<String> = columns.iter()
+            .filter(|column| !existing.iter().any(|existing| existing.name.eq_ignore_ascii_case(column.name)))
+            .map(|column| column.name.to_string())
+            .collect();
+        let extra: Vec<String> = existing.into_iter()
+            .filter(|existing| !columns.iter().any(|column| column.name.eq_ignore_ascii_case(&existing.name)))
+            .map(|existing| existing.name)
+            .collect();
+        if missing.is_empty() && extra.is_empty() {
+            Ok(())
+        } else {
+            Err(KinglerError::SchemaMismatch { missing, extra })
+        }
+    }
+}
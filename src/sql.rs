@@ -0,0 +1,390 @@
+//! Builders rendering the SQL statements run by the backends
+//!
+//! These functions only format strings: they never touch a connection, so the
+//! statement an operation runs can be checked without a database. Table and
+//! column names are inserted as given and values are left as placeholders,
+//! `?` unless the insert and update builders are given a numbering dialect.
+
+use crate::Dialect;
+
+/// Renders a `CREATE TABLE IF NOT EXISTS` statement
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_create_table;
+///
+/// let columns = vec!["id INTEGER PRIMARY KEY".to_string(), "name TEXT".to_string()];
+/// assert_eq!(
+///     build_create_table("users", &columns),
+///     "CREATE TABLE IF NOT EXISTS users (id INTEGER PRIMARY KEY, name TEXT)"
+/// );
+/// ```
+pub fn build_create_table(table: &str, columns: &[String]) -> String {
+    format!("CREATE TABLE IF NOT EXISTS {} ({})", table, columns.join(", "))
+}
+
+/// Renders a `CREATE TEMPORARY TABLE IF NOT EXISTS` statement
+///
+/// A temporary table only lives as long as the connection creating it and is
+/// only visible to that connection.
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_create_temp_table;
+///
+/// let columns = vec!["id INTEGER PRIMARY KEY".to_string(), "total REAL".to_string()];
+/// assert_eq!(
+///     build_create_temp_table("totals", &columns),
+///     "CREATE TEMPORARY TABLE IF NOT EXISTS totals (id INTEGER PRIMARY KEY, total REAL)"
+/// );
+/// ```
+pub fn build_create_temp_table(table: &str, columns: &[String]) -> String {
+    format!("CREATE TEMPORARY TABLE IF NOT EXISTS {} ({})", table, columns.join(", "))
+}
+
+/// Renders an `INSERT` statement with one placeholder per column, spelled by
+/// the dialect
+///
+/// Without columns the record is inserted with `DEFAULT VALUES`, since
+/// `INSERT INTO t () VALUES ()` is invalid.
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_insert;
+/// use kingler::Database;
+///
+/// let columns = vec!["name".to_string(), "age".to_string(), "email".to_string()];
+/// assert_eq!(
+///     build_insert(&Database::Sqlite, "users", &columns),
+///     "INSERT INTO users (name, age, email) VALUES (?, ?, ?)"
+/// );
+/// assert_eq!(
+///     build_insert(&Database::Postgres, "users", &columns),
+///     "INSERT INTO users (name, age, email) VALUES ($1, $2, $3)"
+/// );
+/// assert_eq!(build_insert(&Database::Sqlite, "users", &[]), "INSERT INTO users DEFAULT VALUES");
+/// ```
+pub fn build_insert(dialect: &dyn Dialect, table: &str, columns: &[String]) -> String {
+    if columns.is_empty() {
+        return format!("INSERT INTO {} DEFAULT VALUES", table);
+    }
+
+    let placeholders: Vec<String> = (1..=columns.len())
+        .map(|index| dialect.placeholder(index))
+        .collect();
+    let placeholders = placeholders.join(", ");
+    format!("INSERT INTO {} ({}) VALUES ({})", table, columns.join(", "), placeholders)
+}
+
+/// Renders an `INSERT OR IGNORE` statement, which skips the record instead of
+/// failing when it violates a constraint
+///
+/// This is the SQLite spelling; see [`build_insert`] for the placeholders.
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_insert_or_ignore;
+/// use kingler::Database;
+///
+/// let columns = vec!["email".to_string(), "name".to_string()];
+/// assert_eq!(
+///     build_insert_or_ignore(&Database::Sqlite, "users", &columns),
+///     "INSERT OR IGNORE INTO users (email, name) VALUES (?, ?)"
+/// );
+/// ```
+pub fn build_insert_or_ignore(dialect: &dyn Dialect, table: &str, columns: &[String]) -> String {
+    let insert = build_insert(dialect, table, columns);
+    format!("INSERT OR IGNORE{}", &insert["INSERT".len()..])
+}
+
+/// Renders an `INSERT` statement adding several records at once, with one
+/// group of placeholders per record
+///
+/// The placeholders are numbered across the whole statement, record by record.
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_insert_rows;
+/// use kingler::Database;
+///
+/// let columns = vec!["name".to_string(), "age".to_string()];
+/// assert_eq!(
+///     build_insert_rows(&Database::Sqlite, "users", &columns, 2),
+///     "INSERT INTO users (name, age) VALUES (?, ?), (?, ?)"
+/// );
+/// assert_eq!(
+///     build_insert_rows(&Database::Postgres, "users", &columns, 2),
+///     "INSERT INTO users (name, age) VALUES ($1, $2), ($3, $4)"
+/// );
+/// ```
+pub fn build_insert_rows(dialect: &dyn Dialect, table: &str, columns: &[String], rows: usize) -> String {
+    let groups: Vec<String> = (0..rows)
+        .map(|row| {
+            let placeholders: Vec<String> = (1..=columns.len())
+                .map(|index| dialect.placeholder(row * columns.len() + index))
+                .collect();
+            format!("({})", placeholders.join(", "))
+        })
+        .collect();
+    format!("INSERT INTO {} ({}) VALUES {}", table, columns.join(", "), groups.join(", "))
+}
+
+/// Renders an `INSERT` statement that updates the existing record instead when
+/// it conflicts with one on the given columns
+///
+/// The conflicting record takes the inserted values of every other column. The
+/// columns must be covered by a unique index or constraint, or be the primary
+/// key. This is the SQLite and PostgreSQL spelling, `ON CONFLICT ... DO UPDATE`.
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_upsert;
+/// use kingler::Database;
+///
+/// let columns = vec!["email".to_string(), "name".to_string()];
+/// let conflict = vec!["email".to_string()];
+/// assert_eq!(
+///     build_upsert(&Database::Sqlite, "users", &columns, &conflict),
+///     "INSERT INTO users (email, name) VALUES (?, ?) \
+///      ON CONFLICT (email) DO UPDATE SET name = excluded.name"
+/// );
+/// ```
+pub fn build_upsert(dialect: &dyn Dialect, table: &str, columns: &[String], conflict_columns: &[String]) -> String {
+    let mut updated: Vec<&String> = columns.iter()
+        .filter(|column| !conflict_columns.contains(column))
+        .collect();
+    // Setting a conflict column to itself keeps the update, so the record is
+    // still returned, when every column is part of the conflict target
+    if updated.is_empty() {
+        updated = conflict_columns.iter().take(1).collect();
+    }
+    let assignments: Vec<String> = updated.iter()
+        .map(|column| format!("{} = excluded.{}", column, column))
+        .collect();
+    format!(
+        "{} ON CONFLICT ({}) DO UPDATE SET {}",
+        build_insert(dialect, table, columns),
+        conflict_columns.join(", "),
+        assignments.join(", ")
+    )
+}
+
+/// Renders an `UPDATE` statement setting each column of the records whose key
+/// column equals a value
+///
+/// The placeholders of the new values come first, followed by the key.
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_update;
+/// use kingler::Database;
+///
+/// let columns = vec!["name".to_string(), "age".to_string()];
+/// assert_eq!(
+///     build_update(&Database::Sqlite, "users", &columns, "id"),
+///     "UPDATE users SET name = ?, age = ? WHERE id = ?"
+/// );
+/// assert_eq!(
+///     build_update(&Database::Postgres, "users", &columns, "id"),
+///     "UPDATE users SET name = $1, age = $2 WHERE id = $3"
+/// );
+/// ```
+pub fn build_update(dialect: &dyn Dialect, table: &str, columns: &[String], key_column: &str) -> String {
+    let key = format!("{} = {}", key_column, dialect.placeholder(columns.len() + 1));
+    build_update_where(dialect, table, columns, &[key])
+}
+
+/// Renders an `UPDATE` statement setting each column of the records matching
+/// all the given conditions
+///
+/// The placeholders of the new values come first, numbered from 1, followed by
+/// those of the conditions, which are inserted as written. Without conditions
+/// every record is updated.
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_update_where;
+/// use kingler::Database;
+///
+/// let columns = vec!["active".to_string()];
+/// let conditions = vec!["age < ?".to_string(), "email IS NULL".to_string()];
+/// assert_eq!(
+///     build_update_where(&Database::Sqlite, "users", &columns, &conditions),
+///     "UPDATE users SET active = ? WHERE age < ? AND email IS NULL"
+/// );
+/// assert_eq!(
+///     build_update_where(&Database::Sqlite, "users", &columns, &[]),
+///     "UPDATE users SET active = ?"
+/// );
+/// ```
+pub fn build_update_where(dialect: &dyn Dialect, table: &str, columns: &[String], conditions: &[String]) -> String {
+    let assignments: Vec<String> = columns.iter()
+        .enumerate()
+        .map(|(index, column)| format!("{} = {}", column, dialect.placeholder(index + 1)))
+        .collect();
+
+    let mut sql = format!("UPDATE {} SET {}", table, assignments.join(", "));
+    if !conditions.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+    }
+    sql
+}
+
+/// Renders an `UPDATE` statement setting columns to a different value for each
+/// record, picked by a `CASE` on its key
+///
+/// Each column is given with the number of records setting it, each adding a
+/// `WHEN ? THEN ?` branch for the key and the value, while the other records
+/// keep their value. The placeholders of the branches come first, column by
+/// column, followed by the `keys` placeholders of the `IN` list restricting
+/// the update to the records given.
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_update_cases;
+///
+/// let cases = vec![("name".to_string(), 2), ("age".to_string(), 1)];
+/// assert_eq!(
+///     build_update_cases("users", "id", &cases, 2),
+///     "UPDATE users SET name = CASE id WHEN ? THEN ? WHEN ? THEN ? ELSE name END, \
+///      age = CASE id WHEN ? THEN ? ELSE age END WHERE id IN (?, ?)"
+/// );
+/// ```
+pub fn build_update_cases(table: &str, key_column: &str, cases: &[(String, usize)], keys: usize) -> String {
+    let assignments: Vec<String> = cases.iter()
+        .map(|(column, branches)| {
+            let branches = vec!["WHEN ? THEN ?"; *branches].join(" ");
+            format!("{} = CASE {} {} ELSE {} END", column, key_column, branches, column)
+        })
+        .collect();
+    let keys = vec!["?"; keys].join(", ");
+    format!("UPDATE {} SET {} WHERE {} IN ({})", table, assignments.join(", "), key_column, keys)
+}
+
+/// Renders a `DELETE` statement removing the records whose key column equals a value
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_delete;
+///
+/// assert_eq!(build_delete("users", "id"), "DELETE FROM users WHERE id = ?");
+/// ```
+pub fn build_delete(table: &str, key_column: &str) -> String {
+    format!("DELETE FROM {} WHERE {} = ?", table, key_column)
+}
+
+/// Renders a `DELETE` statement removing the records matching all the given
+/// conditions
+///
+/// Without conditions every record is deleted.
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_delete_where;
+///
+/// let conditions = vec!["user_ref = ?".to_string(), "role_ref = ?".to_string()];
+/// assert_eq!(
+///     build_delete_where("user_role", &conditions),
+///     "DELETE FROM user_role WHERE user_ref = ? AND role_ref = ?"
+/// );
+/// assert_eq!(build_delete_where("user_role", &[]), "DELETE FROM user_role");
+/// ```
+pub fn build_delete_where(table: &str, conditions: &[String]) -> String {
+    let mut sql = format!("DELETE FROM {}", table);
+    if !conditions.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+    }
+    sql
+}
+
+/// Renders an `ALTER TABLE ... RENAME TO` statement
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_rename_table;
+///
+/// assert_eq!(build_rename_table("users", "members"), "ALTER TABLE users RENAME TO members");
+/// ```
+pub fn build_rename_table(table: &str, new_name: &str) -> String {
+    format!("ALTER TABLE {} RENAME TO {}", table, new_name)
+}
+
+/// Renders a PostgreSQL `COMMENT ON COLUMN` statement describing a column
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_comment_on_column;
+///
+/// assert_eq!(
+///     build_comment_on_column("users", "email", "Where the user's receipts go"),
+///     "COMMENT ON COLUMN users.email IS 'Where the user''s receipts go'"
+/// );
+/// ```
+pub fn build_comment_on_column(table: &str, column: &str, comment: &str) -> String {
+    format!("COMMENT ON COLUMN {}.{} IS '{}'", table, column, comment.replace('\'', "''"))
+}
+
+/// Renders a `CREATE INDEX IF NOT EXISTS` statement
+///
+/// With a predicate the index is partial: it only covers the records matching it.
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_create_index;
+///
+/// let columns = vec!["email".to_string()];
+/// assert_eq!(
+///     build_create_index("users_email", "users", &columns, None),
+///     "CREATE INDEX IF NOT EXISTS users_email ON users (email)"
+/// );
+/// assert_eq!(
+///     build_create_index("users_active_email", "users", &columns, Some("active = 1")),
+///     "CREATE INDEX IF NOT EXISTS users_active_email ON users (email) WHERE active = 1"
+/// );
+/// ```
+pub fn build_create_index(name: &str, table: &str, columns: &[String], predicate: Option<&str>) -> String {
+    let mut sql = format!("CREATE INDEX IF NOT EXISTS {} ON {} ({})", name, table, columns.join(", "));
+    if let Some(predicate) = predicate {
+        sql.push_str(" WHERE ");
+        sql.push_str(predicate);
+    }
+    sql
+}
+
+/// Renders a `SELECT` statement
+///
+/// # Arguments
+/// * `table` - Name of the table to query
+/// * `columns` - Columns to select, every column when empty
+/// * `conditions` - Conditions joined with `AND` into the `WHERE` clause,
+///   which is left out when there are none
+///
+/// # Example
+/// ```rust
+/// use kingler::sql::build_select;
+///
+/// assert_eq!(build_select("users", &[], &[]), "SELECT * FROM users");
+///
+/// let columns = vec!["name".to_string()];
+/// let conditions = vec!["age = ?".to_string(), "email IS NULL".to_string()];
+/// assert_eq!(
+///     build_select("users", &columns, &conditions),
+///     "SELECT name FROM users WHERE age = ? AND email IS NULL"
+/// );
+/// ```
+pub fn build_select(table: &str, columns: &[String], conditions: &[String]) -> String {
+    let columns = if columns.is_empty() {
+        "*".to_string()
+    } else {
+        columns.join(", ")
+    };
+
+    let mut sql = format!("SELECT {} FROM {}", columns, table);
+    if !conditions.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+    }
+    sql
+}
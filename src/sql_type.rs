@@ -0,0 +1,40 @@
+/// Backend-independent type of a column
+///
+/// Column metadata carries this type and each [`Dialect`](crate::Dialect)
+/// renders it into its own DDL when the schema is generated.
+///
+/// # Example
+/// ```rust
+/// use kingler::{Database, Dialect, SqlType};
+///
+/// assert_eq!(Database::Sqlite.render(SqlType::Integer), "INTEGER");
+/// assert_eq!(Database::Sqlite.render(SqlType::Real), "REAL");
+/// assert_eq!(Database::Sqlite.render(SqlType::Text), "TEXT");
+/// assert_eq!(Database::Sqlite.render(SqlType::Boolean), "BOOLEAN");
+/// assert_eq!(Database::Sqlite.render(SqlType::Blob), "BLOB");
+/// assert_eq!(Database::Sqlite.render(SqlType::Datetime), "TEXT");
+///
+/// assert_eq!(Database::Mysql.render(SqlType::Integer), "BIGINT");
+/// assert_eq!(Database::Mysql.render(SqlType::Real), "DOUBLE");
+/// assert_eq!(Database::Mysql.render(SqlType::Datetime), "DATETIME");
+///
+/// assert_eq!(Database::Postgres.render(SqlType::Real), "DOUBLE PRECISION");
+/// assert_eq!(Database::Postgres.render(SqlType::Blob), "BYTEA");
+/// assert_eq!(Database::Postgres.render(SqlType::Datetime), "TIMESTAMP");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlType {
+    /// 64-bit integers
+    Integer,
+    /// Floating point numbers
+    Real,
+    /// Text of any length
+    Text,
+    /// Booleans, stored as 0 and 1 where there is no dedicated type
+    Boolean,
+    /// Binary data
+    Blob,
+    /// Dates with a time of day, stored as ISO-8601 text where there is no
+    /// dedicated type
+    Datetime,
+}
@@ -1,3 +1,92 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::{sql, Database, Dialect, KinglerValue, ResultSet};
+
+/// Describes what `Sqlite::create_relationship` changed in the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelationshipChange {
+    /// A junction table with the given name was created
+    CreatedJunctionTable(String),
+    /// A foreign key column was added to a table
+    AddedColumn { table: String, column: String },
+    /// The relationship already existed, nothing was changed
+    Unchanged,
+}
+
+/// What a foreign key does when the record it references is deleted, see
+/// [`Sqlite::create_relationship_on_delete`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnDelete {
+    /// Deletes the referencing records too
+    Cascade,
+    /// Sets the foreign key of the referencing records to NULL
+    SetNull,
+    /// Sets the foreign key of the referencing records to its default
+    SetDefault,
+    /// Refuses to delete a record while it is referenced
+    Restrict,
+    /// Fails the statement if the record is still referenced once it ends,
+    /// SQLite's default
+    NoAction,
+}
+
+impl OnDelete {
+    /// Renders the `ON DELETE` clause of a foreign key
+    pub fn sql(self) -> &'static str {
+        match self {
+            OnDelete::Cascade => "ON DELETE CASCADE",
+            OnDelete::SetNull => "ON DELETE SET NULL",
+            OnDelete::SetDefault => "ON DELETE SET DEFAULT",
+            OnDelete::Restrict => "ON DELETE RESTRICT",
+            OnDelete::NoAction => "ON DELETE NO ACTION",
+        }
+    }
+}
+
+/// A column of a table as reported by [`Sqlite::describe_table`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnDescription {
+    /// Name of the column
+    pub name: String,
+    /// Declared type of the column, as written in the schema
+    pub sql_type: String,
+    /// Whether the column is part of the primary key
+    pub primary_key: bool,
+    /// Description stored with [`Sqlite::set_column_comments`], if any
+    pub comment: Option<String>,
+}
+
+/// Page statistics of a database file, as reported by [`Sqlite::db_stats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbStats {
+    /// Number of pages in the file (`PRAGMA page_count`)
+    pub page_count: u64,
+    /// Size of each page in bytes (`PRAGMA page_size`)
+    pub page_size: u64,
+    /// Number of unused pages (`PRAGMA freelist_count`), reclaimed by `VACUUM`
+    pub freelist_count: u64,
+}
+
+impl DbStats {
+    /// Size of the database in bytes
+    pub fn size_bytes(&self) -> u64 {
+        self.page_count * self.page_size
+    }
+
+    /// Bytes taken by unused pages
+    pub fn free_bytes(&self) -> u64 {
+        self.freelist_count * self.page_size
+    }
+}
+
+/// Table holding the column comments SQLite has no place for in the schema
+pub const COLUMN_META_TABLE: &str = "_kingler_column_meta";
+
+/// Shared slot holding the last statement run and its parameters, see
+/// [`KinglerBuilder::capture_sql`](crate::KinglerBuilder::capture_sql)
+pub(crate) type SqlLog = Arc<Mutex<Option<(String, Vec<KinglerValue>)>>>;
+
 /// Represents a connection to a SQLite database
 /// 
 /// This struct wraps the rusqlite Connection type and provides
@@ -5,6 +94,8 @@
 pub struct Sqlite {
     /// The underlying SQLite connection
     conn: rusqlite::Connection,
+    /// Where the statements run by the connection are recorded, if anywhere
+    pub(crate) sql_log: Option<SqlLog>,
 }
 
 impl Sqlite {
@@ -12,36 +103,72 @@ impl Sqlite {
     /// 
     /// # Arguments
     /// * `database_path` - Path to the SQLite database file. If the file doesn't exist,
-    ///                     it will be created automatically.
+    ///   it will be created automatically.
     /// 
     /// # Returns
     /// * `Result<Self, rusqlite::Error>` - A Result containing either the Sqlite instance
-    ///                                     or a database error
+    ///   or a database error
     /// 
     /// # Example
     /// ```rust
-    /// let db = Sqlite::new("my_database.db".to_string())?;
+    /// # use kingler::sqlite::Sqlite;
+    /// # fn main() -> Result<(), rusqlite::Error> {
+    /// # let path = std::env::temp_dir().join("kingler_sqlite_new.db");
+    /// let db = Sqlite::new(path.to_string_lossy().to_string())?;
+    /// # Ok(())
+    /// # }
     /// ```
     pub fn new(database_path: String) -> Result<Self, rusqlite::Error> {
         let conn = rusqlite::Connection::open(&database_path)?;
         Ok(Sqlite {
             conn,
+            sql_log: None,
         })
     }
 
+    /// Unlocks an encrypted database with its key
+    /// 
+    /// Runs `PRAGMA key` and then reads the schema, since a wrong key is only
+    /// detected once the database is read.
+    #[cfg(feature = "sqlcipher")]
+    pub fn set_key(&self, key: &str) -> Result<(), rusqlite::Error> {
+        self.conn.pragma_update(None, "key", key)?;
+        self.conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))?;
+        Ok(())
+    }
+
+    /// Records a statement about to run when SQL capture is enabled
+    fn record(&self, query: &str, params: &[KinglerValue]) {
+        if let Some(sql_log) = &self.sql_log {
+            *sql_log.lock().unwrap_or_else(PoisonError::into_inner) = Some((query.to_string(), params.to_vec()));
+        }
+    }
+
+    /// The underlying rusqlite connection
+    pub fn connection(&self) -> &rusqlite::Connection {
+        &self.conn
+    }
+
+    /// The backend this connection talks to, which decides how SQL is generated
+    pub fn database(&self) -> Database {
+        Database::Sqlite
+    }
+
     /// Creates a new table in the database if it doesn't already exist
     /// 
     /// # Arguments
     /// * `table_name` - Name of the table to create
     /// * `columns` - Vector of column definitions (e.g., "name TEXT", "age INTEGER")
-    ///               Note: An 'id' column with AUTO INCREMENT is automatically added
     /// 
     /// # Returns
     /// * `Result<(), rusqlite::Error>` - Success (()) or a database error
     /// 
     /// # Example
     /// ```rust
-    /// let db = Sqlite::new("my_database.db")?;
+    /// # use kingler::sqlite::Sqlite;
+    /// # fn main() -> Result<(), rusqlite::Error> {
+    /// # let path = std::env::temp_dir().join("kingler_sqlite_create_table.db");
+    /// let db = Sqlite::new(path.to_string_lossy().to_string())?;
     /// db.create_table(
     ///     "users".to_string(),
     ///     vec![
@@ -49,50 +176,804 @@ impl Sqlite {
     ///         "age INTEGER".to_string()
     ///     ]
     /// )?;
+    /// # Ok(())
+    /// # }
     /// ```
     pub fn create_table(&self, table_name: String, columns: Vec<String>) -> Result<(), rusqlite::Error> {
-        let columns_str = columns.join(", ");
-        let query = format!("CREATE TABLE IF NOT EXISTS {} ({})", table_name, columns_str);
+        let query = sql::build_create_table(&table_name, &columns);
+        self.record(&query, &[]);
         self.conn.execute(&query, [])?;
         
         Ok(())
     }
 
+    /// Creates a new temporary table if it doesn't already exist, living until
+    /// the connection is closed
+    /// 
+    /// # Arguments
+    /// * `table_name` - Name of the table to create
+    /// * `columns` - Vector of column definitions
+    /// * `without_rowid` - Whether the table is created `WITHOUT ROWID`
+    pub fn create_temp_table(&self, table_name: String, columns: Vec<String>, without_rowid: bool) -> Result<(), rusqlite::Error> {
+        let mut query = sql::build_create_temp_table(&table_name, &columns);
+        if without_rowid {
+            query.push_str(" WITHOUT ROWID");
+        }
+        self.record(&query, &[]);
+        self.conn.execute(&query, [])?;
+
+        Ok(())
+    }
+
+    /// Drops a table if it exists, along with its records and indexes
+    /// 
+    /// # Arguments
+    /// * `table_name` - Name of the table to drop
+    pub fn drop_table(&self, table_name: String) -> Result<(), rusqlite::Error> {
+        let query = format!("DROP TABLE IF EXISTS {}", table_name);
+        self.record(&query, &[]);
+        self.conn.execute(&query, [])?;
+
+        Ok(())
+    }
+
+    /// Creates a new `WITHOUT ROWID` table if it doesn't already exist
+    /// 
+    /// Such a table is stored in the order of its primary key, which must be
+    /// declared among the columns and can't use `AUTOINCREMENT`.
+    /// 
+    /// # Returns
+    /// * `Result<(), rusqlite::Error>` - Success (()) or a database error
+    pub fn create_table_without_rowid(&self, table_name: String, columns: Vec<String>) -> Result<(), rusqlite::Error> {
+        let query = format!("{} WITHOUT ROWID", sql::build_create_table(&table_name, &columns));
+        self.record(&query, &[]);
+        self.conn.execute(&query, [])?;
+
+        Ok(())
+    }
+
     /// Inserts a new record into a specified table
     /// 
     /// # Arguments
     /// * `table_name` - Name of the target table
     /// * `columns` - Vector of column names to insert into
-    /// * `values` - Vector of values to insert (must match columns in length)
+    /// * `values` - Vector of values to bind (must match columns in length).
+    ///   When both are empty the record is inserted with `DEFAULT VALUES`.
     /// 
     /// # Returns
-    /// * `Result<i64, rusqlite::Error>` - Success (()) or a database error
+    /// * `Result<i64, rusqlite::Error>` - The rowid of the inserted record or a database error
     /// 
     /// # Example
     /// ```rust
-    /// let db = Sqlite::new("my_database.db")?;
-    /// db.insert(
+    /// # use kingler::{sqlite::Sqlite, KinglerValue};
+    /// # fn main() -> Result<(), rusqlite::Error> {
+    /// # let path = std::env::temp_dir().join("kingler_sqlite_insert.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Sqlite::new(path.to_string_lossy().to_string())?;
+    /// # db.create_table("users".to_string(), vec!["name TEXT".to_string(), "age INTEGER".to_string()])?;
+    /// let id = db.insert(
     ///     "users".to_string(),
     ///     vec!["name".to_string(), "age".to_string()],
-    ///     vec!["John".to_string(), "30".to_string()]
+    ///     vec![KinglerValue::from("John"), KinglerValue::from(30)]
     /// )?;
+    /// assert_eq!(id, 1);
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn insert(&self, table_name: String, columns: Vec<String>, values: Vec<String>) -> Result<i64, rusqlite::Error> {
-        let placeholders = vec!["?"; columns.len()].join(", ");
-        let columns_str = columns.join(", ");
+    pub fn insert(&self, table_name: String, columns: Vec<String>, values: Vec<KinglerValue>) -> Result<i64, rusqlite::Error> {
+        let query = sql::build_insert(&self.database(), &table_name, &columns);
+        self.record(&query, &values);
+        self.conn.execute(&query, rusqlite::params_from_iter(values.iter()))?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Inserts a new record into a table unless it violates a constraint, such
+    /// as a unique column already holding one of its values
+    /// 
+    /// # Returns
+    /// * `Result<Option<i64>, rusqlite::Error>` - The rowid of the inserted record,
+    ///   `None` if it was skipped, or a database error
+    pub fn insert_or_ignore(
+        &self,
+        table_name: String,
+        columns: Vec<String>,
+        values: Vec<KinglerValue>
+    ) -> Result<Option<i64>, rusqlite::Error> {
+        let query = sql::build_insert_or_ignore(&self.database(), &table_name, &columns);
+        self.record(&query, &values);
+        let changes = self.conn.execute(&query, rusqlite::params_from_iter(values.iter()))?;
+        Ok((changes > 0).then(|| self.conn.last_insert_rowid()))
+    }
+
+    /// Inserts several records sharing the same columns with a single statement
+    /// 
+    /// # Arguments
+    /// * `table_name` - Name of the target table
+    /// * `columns` - Vector of column names to insert into
+    /// * `values` - Values of every record one after the other, `columns.len()`
+    ///   per record
+    /// 
+    /// # Returns
+    /// * `Result<usize, rusqlite::Error>` - The number of inserted records or a database error
+    pub fn insert_rows(&self, table_name: String, columns: Vec<String>, values: Vec<KinglerValue>) -> Result<usize, rusqlite::Error> {
+        let rows = values.len() / columns.len().max(1);
+        let query = sql::build_insert_rows(&self.database(), &table_name, &columns, rows);
+        self.record(&query, &values);
+        self.conn.execute(&query, rusqlite::params_from_iter(values.iter()))
+    }
+
+    /// Inserts several records sharing the same columns with a single statement
+    /// and returns the value of the key column of each, in insertion order
+    /// 
+    /// Requires SQLite 3.35 or later, see [`Sqlite::supports_returning`].
+    /// 
+    /// # Returns
+    /// * `Result<Vec<KinglerValue>, rusqlite::Error>` - The keys of the inserted records or a database error
+    pub fn insert_rows_returning(
+        &self,
+        table_name: String,
+        columns: Vec<String>,
+        values: Vec<KinglerValue>,
+        key_column: String
+    ) -> Result<Vec<KinglerValue>, rusqlite::Error> {
+        let rows = values.len() / columns.len().max(1);
+        let query = format!(
+            "{} RETURNING {}",
+            sql::build_insert_rows(&self.database(), &table_name, &columns, rows),
+            key_column
+        );
+        self.record(&query, &values);
+        let mut stmt = self.conn.prepare(&query)?;
+        let keys = stmt.query_map(rusqlite::params_from_iter(values.iter()), |row| {
+            Ok(KinglerValue::from(row.get_ref(0)?))
+        })?;
+        keys.collect()
+    }
+
+    /// Inserts a new record into a table and returns the value of its key column
+    /// 
+    /// Unlike [`Sqlite::insert`] the key is read back with `RETURNING`, so it is
+    /// the stored primary key whatever its type, including in `WITHOUT ROWID`
+    /// tables. Requires SQLite 3.35 or later, see [`Sqlite::supports_returning`].
+    /// 
+    /// # Returns
+    /// * `Result<KinglerValue, rusqlite::Error>` - The key of the inserted record or a database error
+    pub fn insert_returning(
+        &self,
+        table_name: String,
+        columns: Vec<String>,
+        values: Vec<KinglerValue>,
+        key_column: String
+    ) -> Result<KinglerValue, rusqlite::Error> {
         let query = format!(
-            "INSERT INTO {} ({}) VALUES ({})",
-            table_name, columns_str, placeholders
+            "{} RETURNING {}",
+            sql::build_insert(&self.database(), &table_name, &columns),
+            key_column
         );
+        self.record(&query, &values);
+        self.conn.query_row(&query, rusqlite::params_from_iter(values.iter()), |row| {
+            Ok(KinglerValue::from(row.get_ref(0)?))
+        })
+    }
+
+    /// Inserts a record, or updates the record it conflicts with on the given
+    /// columns, see [`sql::build_upsert`]
+    /// 
+    /// # Returns
+    /// * `Result<usize, rusqlite::Error>` - The number of inserted or updated records
+    ///   or a database error
+    pub fn upsert(
+        &self,
+        table_name: String,
+        columns: Vec<String>,
+        values: Vec<KinglerValue>,
+        conflict_columns: Vec<String>
+    ) -> Result<usize, rusqlite::Error> {
+        let query = sql::build_upsert(&self.database(), &table_name, &columns, &conflict_columns);
+        self.record(&query, &values);
+        self.conn.execute(&query, rusqlite::params_from_iter(values.iter()))
+    }
+
+    /// Inserts a record, or updates the record it conflicts with on the given
+    /// columns, and returns the value of its key column
+    /// 
+    /// Requires SQLite 3.35 or later, see [`Sqlite::supports_returning`].
+    /// 
+    /// # Returns
+    /// * `Result<KinglerValue, rusqlite::Error>` - The key of the inserted or updated
+    ///   record or a database error
+    pub fn upsert_returning(
+        &self,
+        table_name: String,
+        columns: Vec<String>,
+        values: Vec<KinglerValue>,
+        conflict_columns: Vec<String>,
+        key_column: String
+    ) -> Result<KinglerValue, rusqlite::Error> {
+        let query = format!(
+            "{} RETURNING {}",
+            sql::build_upsert(&self.database(), &table_name, &columns, &conflict_columns),
+            key_column
+        );
+        self.record(&query, &values);
+        self.conn.query_row(&query, rusqlite::params_from_iter(values.iter()), |row| {
+            Ok(KinglerValue::from(row.get_ref(0)?))
+        })
+    }
+
+    /// Updates the records of a table whose key column equals the given value
+    /// 
+    /// # Arguments
+    /// * `table_name` - Name of the target table
+    /// * `columns` - Vector of column names to update
+    /// * `values` - Vector of new values (must match columns in length)
+    /// * `key_column` - Name of the column identifying the records to update
+    /// * `key` - Value of the key column
+    /// 
+    /// # Returns
+    /// * `Result<usize, rusqlite::Error>` - The number of updated records or a database error
+    pub fn update(
+        &self,
+        table_name: String,
+        columns: Vec<String>,
+        values: Vec<KinglerValue>,
+        key_column: String,
+        key: KinglerValue
+    ) -> Result<usize, rusqlite::Error> {
+        let query = sql::build_update(&self.database(), &table_name, &columns, &key_column);
+
+        let params: Vec<KinglerValue> = values.into_iter().chain(std::iter::once(key)).collect();
+        self.record(&query, &params);
+        self.conn.execute(&query, rusqlite::params_from_iter(params.iter()))
+    }
+
+    /// Updates the records of a table whose key column equals the given value and
+    /// returns them as stored after the update
+    /// 
+    /// Uses `RETURNING`, which requires SQLite 3.35 or later, see
+    /// [`Sqlite::supports_returning`].
+    /// 
+    /// # Returns
+    /// * `Result<Vec<serde_json::Map<String, serde_json::Value>>, rusqlite::Error>` - The updated
+    ///   records keyed by column name, or a database error
+    pub fn update_returning(
+        &self,
+        table_name: String,
+        columns: Vec<String>,
+        values: Vec<KinglerValue>,
+        key_column: String,
+        key: KinglerValue
+    ) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, rusqlite::Error> {
+        let query = format!("{} RETURNING *", sql::build_update(&self.database(), &table_name, &columns, &key_column));
+
+        let params = values.into_iter().chain(std::iter::once(key)).collect();
+        self.query(&query, params)
+    }
+
+    /// Updates the records of a table matching all the given equality filters
+    /// 
+    /// # Arguments
+    /// * `table_name` - Name of the target table
+    /// * `columns` - Vector of column names to update
+    /// * `values` - Vector of new values (must match columns in length)
+    /// * `filters` - Column names paired with the value they must be equal to,
+    ///   as in [`Sqlite::find_where`]. An empty vector updates every record.
+    /// 
+    /// # Returns
+    /// * `Result<usize, rusqlite::Error>` - The number of updated records or a database error
+    pub fn update_where(
+        &self,
+        table_name: String,
+        columns: Vec<String>,
+        values: Vec<KinglerValue>,
+        filters: Vec<(String, KinglerValue)>
+    ) -> Result<usize, rusqlite::Error> {
+        let (conditions, filter_params) = Self::equality_conditions(filters);
+        let query = sql::build_update_where(&self.database(), &table_name, &columns, &conditions);
+
+        let params: Vec<KinglerValue> = values.into_iter().chain(filter_params).collect();
+        self.record(&query, &params);
+        self.conn.execute(&query, rusqlite::params_from_iter(params.iter()))
+    }
 
-        // Convert string values to params
-        let params: Vec<&dyn rusqlite::ToSql> = values.iter()
-            .map(|v| v as &dyn rusqlite::ToSql)
+    /// Updates several records of a table to different values in a single
+    /// statement, see [`sql::build_update_cases`]
+    /// 
+    /// # Arguments
+    /// * `table_name` - Name of the target table
+    /// * `key_column` - Name of the key column
+    /// * `updates` - The key of each record paired with the columns to set and
+    ///   their new values. Records without columns are left out.
+    /// 
+    /// # Returns
+    /// * `Result<usize, rusqlite::Error>` - The number of updated records or a database error
+    pub fn update_cases(
+        &self,
+        table_name: String,
+        key_column: String,
+        updates: Vec<(KinglerValue, Vec<(String, KinglerValue)>)>
+    ) -> Result<usize, rusqlite::Error> {
+        let updates: Vec<_> = updates.into_iter()
+            .filter(|(_, set)| !set.is_empty())
             .collect();
+        if updates.is_empty() {
+            return Ok(0);
+        }
 
-        self.conn.execute(&query, rusqlite::params_from_iter(params))?;
-        Ok(self.conn.last_insert_rowid())
+        // The columns in the order they first appear, each with its branches
+        let mut cases: Vec<(String, Vec<KinglerValue>)> = Vec::new();
+        for (key, set) in &updates {
+            for (column, value) in set {
+                let index = match cases.iter().position(|(name, _)| name == column) {
+                    Some(index) => index,
+                    None => {
+                        cases.push((column.clone(), Vec::new()));
+                        cases.len() - 1
+                    }
+                };
+                cases[index].1.extend([key.clone(), value.clone()]);
+            }
+        }
+
+        let branches: Vec<(String, usize)> = cases.iter()
+            .map(|(column, params)| (column.clone(), params.len() / 2))
+            .collect();
+        let query = sql::build_update_cases(&table_name, &key_column, &branches, updates.len());
+        let params: Vec<KinglerValue> = cases.into_iter()
+            .flat_map(|(_, params)| params)
+            .chain(updates.into_iter().map(|(key, _)| key))
+            .collect();
+        self.record(&query, &params);
+        self.conn.execute(&query, rusqlite::params_from_iter(params.iter()))
+    }
+
+    /// Deletes the records of a table whose key column equals the given value
+    /// 
+    /// # Returns
+    /// * `Result<usize, rusqlite::Error>` - The number of deleted records or a database error
+    pub fn delete(&self, table_name: String, key_column: String, key: KinglerValue) -> Result<usize, rusqlite::Error> {
+        let query = sql::build_delete(&table_name, &key_column);
+        self.record(&query, std::slice::from_ref(&key));
+        self.conn.execute(&query, [key])
+    }
+
+    /// Deletes the records of a table matching all the given equality filters
+    /// 
+    /// # Arguments
+    /// * `table_name` - Name of the target table
+    /// * `filters` - Column names paired with the value they must be equal to,
+    ///   as in [`Sqlite::find_where`]. An empty vector deletes every record.
+    /// 
+    /// # Returns
+    /// * `Result<usize, rusqlite::Error>` - The number of deleted records or a database error
+    pub fn delete_where(&self, table_name: String, filters: Vec<(String, KinglerValue)>) -> Result<usize, rusqlite::Error> {
+        let (conditions, params) = Self::equality_conditions(filters);
+        let query = sql::build_delete_where(&table_name, &conditions);
+        self.record(&query, &params);
+        self.conn.execute(&query, rusqlite::params_from_iter(params.iter()))
     }
+
+    /// Deletes the records of a table whose key column equals any of the given values
+    /// 
+    /// # Arguments
+    /// * `table_name` - Name of the target table
+    /// * `key_column` - Name of the key column
+    /// * `keys` - Values of the keys, each bound to its own placeholder. An
+    ///   empty vector deletes nothing.
+    /// 
+    /// # Returns
+    /// * `Result<usize, rusqlite::Error>` - The number of deleted records or a database error
+    pub fn delete_in(&self, table_name: String, key_column: String, keys: Vec<KinglerValue>) -> Result<usize, rusqlite::Error> {
+        if keys.is_empty() {
+            return Ok(0);
+        }
+        let placeholders = vec!["?"; keys.len()].join(", ");
+        let condition = format!("{} IN ({})", key_column, placeholders);
+        let query = sql::build_delete_where(&table_name, &[condition]);
+        self.record(&query, &keys);
+        self.conn.execute(&query, rusqlite::params_from_iter(keys.iter()))
+    }
+
+    /// Resets the AUTOINCREMENT counter of a table so its next id starts over
+    /// 
+    /// Tables without AUTOINCREMENT have no counter, in which case nothing is
+    /// done, including when the database has no `sqlite_sequence` table at all.
+    /// 
+    /// # Arguments
+    /// * `table_name` - Unquoted name of the table
+    pub fn reset_sequence(&self, table_name: &str) -> Result<(), rusqlite::Error> {
+        if !self.table_exists("sqlite_sequence")? {
+            return Ok(());
+        }
+        let query = "DELETE FROM sqlite_sequence WHERE name = ?";
+        let params = [KinglerValue::from(table_name)];
+        self.record(query, &params);
+        self.conn.execute(query, rusqlite::params_from_iter(params.iter()))?;
+        Ok(())
+    }
+
+    /// Deletes the records of a table whose key column equals the given value and
+    /// returns them as they were before being deleted
+    /// 
+    /// Uses `RETURNING`, which requires SQLite 3.35 or later, see
+    /// [`Sqlite::supports_returning`].
+    /// 
+    /// # Returns
+    /// * `Result<Vec<serde_json::Map<String, serde_json::Value>>, rusqlite::Error>` - The deleted
+    ///   records keyed by column name, or a database error
+    pub fn delete_returning(
+        &self,
+        table_name: String,
+        key_column: String,
+        key: KinglerValue
+    ) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, rusqlite::Error> {
+        let query = format!("{} RETURNING *", sql::build_delete(&table_name, &key_column));
+        self.query(&query, vec![key])
+    }
+
+    /// Whether the linked SQLite library supports `RETURNING` clauses (3.35 or later)
+    pub fn supports_returning(&self) -> bool {
+        rusqlite::version_number() >= 3_035_000
+    }
+
+    /// Selects the records of a table matching all the given equality filters
+    /// 
+    /// # Arguments
+    /// * `table_name` - Name of the table to query
+    /// * `filters` - Column names paired with the value they must be equal to.
+    ///   A `KinglerValue::Null` value matches NULL columns. An empty vector selects
+    ///   every record.
+    /// 
+    /// # Returns
+    /// * `Result<Vec<serde_json::Map<String, serde_json::Value>>, rusqlite::Error>` - The matching
+    ///   records keyed by column name, or a database error
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::{sqlite::Sqlite, KinglerValue};
+    /// # fn main() -> Result<(), rusqlite::Error> {
+    /// # let path = std::env::temp_dir().join("kingler_sqlite_find_where.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Sqlite::new(path.to_string_lossy().to_string())?;
+    /// # db.create_table("users".to_string(), vec!["name TEXT".to_string()])?;
+    /// # db.insert("users".to_string(), vec!["name".to_string()], vec![KinglerValue::from("John")])?;
+    /// let rows = db.find_where(
+    ///     "users".to_string(),
+    ///     vec![("name".to_string(), KinglerValue::from("John"))]
+    /// )?;
+    /// assert_eq!(rows[0]["name"], "John");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_where(
+        &self,
+        table_name: String,
+        filters: Vec<(String, KinglerValue)>
+    ) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, rusqlite::Error> {
+        let (conditions, params) = Self::equality_conditions(filters);
+        let query = sql::build_select(&table_name, &[], &conditions);
+        self.query(&query, params)
+    }
+
+    /// Turns equality filters into WHERE conditions and the values bound to them
+    fn equality_conditions(filters: Vec<(String, KinglerValue)>) -> (Vec<String>, Vec<KinglerValue>) {
+        // `col = NULL` never matches, so null values are compared with IS NULL
+        let conditions = filters.iter()
+            .map(|(column, value)| match value {
+                KinglerValue::Null => format!("{} IS NULL", column),
+                _ => format!("{} = ?", column),
+            })
+            .collect();
+        let params = filters.into_iter()
+            .map(|(_, value)| value)
+            .filter(|value| *value != KinglerValue::Null)
+            .collect();
+        (conditions, params)
+    }
+
+    /// Runs a SELECT statement and collects every row it returns
+    /// 
+    /// # Arguments
+    /// * `query` - The SELECT statement, using `?` placeholders for its parameters
+    /// * `params` - Values bound to the placeholders, in order
+    /// 
+    /// # Returns
+    /// * `Result<Vec<serde_json::Map<String, serde_json::Value>>, rusqlite::Error>` - The rows
+    ///   keyed by column name, or a database error
+    pub fn query(
+        &self,
+        query: &str,
+        params: Vec<KinglerValue>
+    ) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, rusqlite::Error> {
+        self.record(query, &params);
+        let mut stmt = self.conn.prepare(query)?;
+        let column_names: Vec<String> = stmt.column_names()
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        let mut rows = stmt.query(rusqlite::params_from_iter(params.iter()))?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut record = serde_json::Map::new();
+            for (index, name) in column_names.iter().enumerate() {
+                record.insert(name.clone(), Self::to_json(row.get_ref(index)?));
+            }
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    /// Runs a SELECT statement and hands each row it returns to `f` as soon as
+    /// it is read, without collecting them
+    /// 
+    /// Stops at the first error returned by `f`, which is returned.
+    pub fn query_each<E: From<rusqlite::Error>>(
+        &self,
+        query: &str,
+        params: Vec<KinglerValue>,
+        mut f: impl FnMut(serde_json::Map<String, serde_json::Value>) -> Result<(), E>,
+    ) -> Result<(), E> {
+        self.record(query, &params);
+        let mut stmt = self.conn.prepare(query)?;
+        let column_names: Vec<String> = stmt.column_names()
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        let mut rows = stmt.query(rusqlite::params_from_iter(params.iter()))?;
+        while let Some(row) = rows.next()? {
+            let mut record = serde_json::Map::new();
+            for (index, name) in column_names.iter().enumerate() {
+                record.insert(name.clone(), Self::to_json(row.get_ref(index)?));
+            }
+            f(record)?;
+        }
+        Ok(())
+    }
+
+    /// Runs a SELECT statement and collects the first column of every row it
+    /// returns, converted to `V`
+    /// 
+    /// A value that can't be converted, such as a NULL read as a `String`, fails
+    /// with `rusqlite::Error::InvalidColumnType`.
+    pub fn query_column<V: rusqlite::types::FromSql>(
+        &self,
+        query: &str,
+        params: Vec<KinglerValue>
+    ) -> Result<Vec<V>, rusqlite::Error> {
+        self.record(query, &params);
+        let mut stmt = self.conn.prepare(query)?;
+        let values = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| row.get(0))?;
+        values.collect()
+    }
+
+    /// Runs a statement and collects every row it returns as typed values,
+    /// along with the column names it reports
+    pub fn query_result_set(&self, query: &str, params: Vec<KinglerValue>) -> Result<ResultSet, rusqlite::Error> {
+        self.record(query, &params);
+        let mut stmt = self.conn.prepare(query)?;
+        let column_names: Vec<String> = stmt.column_names()
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        let mut rows = stmt.query(rusqlite::params_from_iter(params.iter()))?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            let values = (0..column_names.len())
+                .map(|index| row.get_ref(index).map(KinglerValue::from))
+                .collect::<Result<Vec<KinglerValue>, rusqlite::Error>>()?;
+            records.push(values);
+        }
+        Ok(ResultSet::new(column_names, records))
+    }
+
+    /// Runs a SELECT statement and collects every row it returns as typed values
+    /// 
+    /// # Arguments
+    /// * `query` - The SELECT statement, using `?` placeholders for its parameters
+    /// * `params` - Values bound to the placeholders, in order
+    /// 
+    /// # Returns
+    /// * `Result<Vec<HashMap<String, KinglerValue>>, rusqlite::Error>` - The rows keyed by
+    ///   the column names reported by the statement, or a database error
+    pub fn query_values(
+        &self,
+        query: &str,
+        params: Vec<KinglerValue>
+    ) -> Result<Vec<HashMap<String, KinglerValue>>, rusqlite::Error> {
+        self.record(query, &params);
+        let mut stmt = self.conn.prepare(query)?;
+        let column_names: Vec<String> = stmt.column_names()
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        let mut rows = stmt.query(rusqlite::params_from_iter(params.iter()))?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut record = HashMap::new();
+            for (index, name) in column_names.iter().enumerate() {
+                record.insert(name.clone(), KinglerValue::from(row.get_ref(index)?));
+            }
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    /// Converts a SQLite value into its JSON representation so records can be
+    /// deserialized with serde
+    fn to_json(value: rusqlite::types::ValueRef<'_>) -> serde_json::Value {
+        use rusqlite::types::ValueRef;
+
+        match value {
+            ValueRef::Null => serde_json::Value::Null,
+            ValueRef::Integer(i) => serde_json::Value::from(i),
+            ValueRef::Real(f) => serde_json::Value::from(f),
+            ValueRef::Text(t) => serde_json::Value::from(String::from_utf8_lossy(t).into_owned()),
+            ValueRef::Blob(b) => serde_json::Value::from(b.to_vec()),
+        }
+    }
+
+    /// Creates an index on columns of a table if it doesn't already exist
+    /// 
+    /// # Arguments
+    /// * `index_name` - Name of the index
+    /// * `table_name` - Name of the indexed table
+    /// * `columns` - Indexed columns, in order
+    /// * `predicate` - Condition limiting the index to the records matching it
+    pub fn create_index(
+        &self,
+        index_name: String,
+        table_name: String,
+        columns: Vec<String>,
+        predicate: Option<String>
+    ) -> Result<(), rusqlite::Error> {
+        let query = sql::build_create_index(&index_name, &table_name, &columns, predicate.as_deref());
+        self.record(&query, &[]);
+        self.conn.execute(&query, [])?;
+        Ok(())
+    }
+
+    /// Renames a table, keeping its records
+    /// 
+    /// # Arguments
+    /// * `table_name` - Current name of the table
+    /// * `new_name` - Name the table is renamed to
+    pub fn rename_table(&self, table_name: String, new_name: String) -> Result<(), rusqlite::Error> {
+        let query = sql::build_rename_table(&table_name, &new_name);
+        self.record(&query, &[]);
+        self.conn.execute(&query, [])?;
+        Ok(())
+    }
+
+    /// Opens a handle for incremental I/O on a BLOB value
+    /// 
+    /// # Arguments
+    /// * `table_name` - Name of the table holding the value
+    /// * `column` - Name of the BLOB column
+    /// * `rowid` - Rowid of the record holding the value
+    /// * `read_only` - Whether the handle can only read
+    pub fn blob_open(
+        &self,
+        table_name: &str,
+        column: &str,
+        rowid: i64,
+        read_only: bool
+    ) -> Result<rusqlite::blob::Blob<'_>, rusqlite::Error> {
+        self.conn.blob_open(rusqlite::DatabaseName::Main, table_name, column, rowid, read_only)
+    }
+
+    /// Checks whether a table exists in the database
+    pub fn table_exists(&self, table_name: &str) -> Result<bool, rusqlite::Error> {
+        let count: i64 = self.conn.query_row(
+            "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = ?",
+            [table_name],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Checks whether a table has a column with the given name
+    pub fn column_exists(&self, table_name: &str, column_name: &str) -> Result<bool, rusqlite::Error> {
+        let count: i64 = self.conn.query_row(
+            "SELECT count(*) FROM pragma_table_info(?) WHERE name = ?",
+            [table_name, column_name],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Stores descriptions of a table's columns in [`COLUMN_META_TABLE`],
+    /// creating it when missing and replacing the columns' previous comments
+    pub fn set_column_comments(&self, table_name: &str, comments: &[(&str, &str)]) -> Result<(), rusqlite::Error> {
+        let query = format!(
+            "CREATE TABLE IF NOT EXISTS {} (table_name TEXT, column_name TEXT, comment TEXT, \
+             PRIMARY KEY (table_name, column_name))",
+            COLUMN_META_TABLE
+        );
+        self.record(&query, &[]);
+        self.conn.execute(&query, [])?;
+
+        let query = format!(
+            "INSERT OR REPLACE INTO {} (table_name, column_name, comment) VALUES (?, ?, ?)",
+            COLUMN_META_TABLE
+        );
+        for (column_name, comment) in comments {
+            let values = vec![
+                KinglerValue::from(table_name),
+                KinglerValue::from(*column_name),
+                KinglerValue::from(*comment),
+            ];
+            self.record(&query, &values);
+            self.conn.execute(&query, rusqlite::params_from_iter(values.iter()))?;
+        }
+        Ok(())
+    }
+
+    /// Lists the columns of a table in order, with the comments stored in
+    /// [`COLUMN_META_TABLE`]
+    /// 
+    /// A table that doesn't exist has no columns.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::sqlite::{ColumnDescription, Sqlite};
+    /// # fn main() -> Result<(), rusqlite::Error> {
+    /// # let path = std::env::temp_dir().join("kingler_sqlite_describe_table.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Sqlite::new(path.to_string_lossy().to_string())?;
+    /// db.create_table("users".to_string(), vec!["id INTEGER PRIMARY KEY".to_string(), "name TEXT".to_string()])?;
+    /// db.set_column_comments("users", &[("name", "Full name")])?;
+    /// 
+    /// let columns = db.describe_table("users")?;
+    /// assert_eq!(columns[1], ColumnDescription {
+    ///     name: "name".to_string(),
+    ///     sql_type: "TEXT".to_string(),
+    ///     primary_key: false,
+    ///     comment: Some("Full name".to_string()),
+    /// });
+    /// assert_eq!(columns[0].comment, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn describe_table(&self, table_name: &str) -> Result<Vec<ColumnDescription>, rusqlite::Error> {
+        let has_comments = self.table_exists(COLUMN_META_TABLE)?;
+        let query = if has_comments {
+            format!(
+                "SELECT info.name, info.type, info.pk, meta.comment FROM pragma_table_info(?1) AS info \
+                 LEFT JOIN {} AS meta ON meta.table_name = ?1 AND meta.column_name = info.name \
+                 ORDER BY info.cid",
+                COLUMN_META_TABLE
+            )
+        } else {
+            "SELECT name, type, pk, NULL FROM pragma_table_info(?1) ORDER BY cid".to_string()
+        };
+        let mut stmt = self.conn.prepare(&query)?;
+        let columns = stmt.query_map([table_name], |row| {
+            Ok(ColumnDescription {
+                name: row.get(0)?,
+                sql_type: row.get(1)?,
+                primary_key: row.get::<_, i64>(2)? > 0,
+                comment: row.get(3)?,
+            })
+        })?;
+        columns.collect()
+    }
+
+    /// Name of the junction table created for a many-to-many relationship
+    /// between two tables, `{table1}_{table2}` in lowercase
+    pub fn junction_table_name(table_name1: &str, table_name2: &str) -> String {
+        format!("{}_{}", table_name1.to_lowercase(), table_name2.to_lowercase())
+    }
+
+    /// Name of the column referencing a table in the tables changed by
+    /// [`Sqlite::create_relationship`], `{table}_ref` in lowercase
+    pub fn reference_column_name(table_name: &str) -> String {
+        format!("{}_ref", table_name.to_lowercase())
+    }
+
     /// Creates a database relationship between two tables
     /// 
     /// # Arguments
@@ -105,37 +986,69 @@ impl Sqlite {
     ///     - "ONE_TO_MANY": Adds a foreign key to table2 (the "many" side)
     ///     - "ONE_TO_ONE": Adds a unique foreign key to table1
     /// 
+    /// Creating a relationship that already exists changes nothing.
+    /// 
     /// # Returns
-    /// * `Result<(), rusqlite::Error>` - Success (()) or a database error
+    /// * `Result<RelationshipChange, rusqlite::Error>` - What was changed in the schema
+    ///   or a database error
     /// 
     /// # Examples
     /// ```rust
+    /// # use kingler::sqlite::{RelationshipChange, Sqlite};
+    /// # fn main() -> Result<(), rusqlite::Error> {
+    /// # let path = std::env::temp_dir().join("kingler_sqlite_create_relationship.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// # let db = Sqlite::new(path.to_string_lossy().to_string())?;
+    /// # for table in ["users", "roles", "departments", "employees", "profiles"] {
+    /// #     db.create_table(table.to_string(), vec!["id INTEGER PRIMARY KEY AUTOINCREMENT".to_string()])?;
+    /// # }
     /// // Create a many-to-many relationship between users and roles
-    /// db.create_relationship(
+    /// let change = db.create_relationship(
     ///     "users".to_string(),
     ///     "roles".to_string(),
     ///     "id".to_string(),
     ///     "id".to_string(),
     ///     "MANY_TO_MANY".to_string()
     /// )?;
+    /// assert_eq!(change, RelationshipChange::CreatedJunctionTable("users_roles".to_string()));
     /// 
     /// // Create a one-to-many relationship between departments and employees
-    /// db.create_relationship(
+    /// let change = db.create_relationship(
     ///     "departments".to_string(),
     ///     "employees".to_string(),
     ///     "id".to_string(),
     ///     "id".to_string(),
     ///     "ONE_TO_MANY".to_string()
     /// )?;
+    /// assert_eq!(change, RelationshipChange::AddedColumn {
+    ///     table: "employees".to_string(),
+    ///     column: "departments_ref".to_string(),
+    /// });
     /// 
     /// // Create a one-to-one relationship between users and profiles
-    /// db.create_relationship(
+    /// let change = db.create_relationship(
     ///     "users".to_string(),
     ///     "profiles".to_string(),
     ///     "id".to_string(),
     ///     "id".to_string(),
     ///     "ONE_TO_ONE".to_string()
     /// )?;
+    /// assert_eq!(change, RelationshipChange::AddedColumn {
+    ///     table: "users".to_string(),
+    ///     column: "profiles_ref".to_string(),
+    /// });
+    /// 
+    /// // Running it again is a no-op
+    /// let change = db.create_relationship(
+    ///     "users".to_string(),
+    ///     "profiles".to_string(),
+    ///     "id".to_string(),
+    ///     "id".to_string(),
+    ///     "ONE_TO_ONE".to_string()
+    /// )?;
+    /// assert_eq!(change, RelationshipChange::Unchanged);
+    /// # Ok(())
+    /// # }
     /// ```
     /// 
     /// # Details
@@ -150,7 +1063,7 @@ impl Sqlite {
     /// 
     /// ## One-to-One
     /// Adds a unique foreign key to the first table (table_name1) referencing
-    /// the second table (table_name2). The UNIQUE constraint ensures that each
+    /// the second table (table_name2). A unique index ensures that each
     /// record in table2 can only be associated with one record in table1.
     /// 
     /// # Errors
@@ -167,49 +1080,209 @@ impl Sqlite {
         column1: String,
         column2: String,
         relation_type: String
-    ) -> Result<(), rusqlite::Error> {
+    ) -> Result<RelationshipChange, rusqlite::Error> {
+        self.create_relationship_on_delete(table_name1, table_name2, column1, column2, relation_type, None)
+    }
+
+    /// Creates a database relationship between two tables whose foreign keys
+    /// take the given action when the record they reference is deleted
+    /// 
+    /// See [`Sqlite::create_relationship`] for the relationship types. In a
+    /// junction table both references take the action. Without an action the
+    /// foreign keys are plain references. SQLite only enforces foreign keys on
+    /// connections that enabled them with `PRAGMA foreign_keys = ON`.
+    /// 
+    /// # Example
+    /// ```rust
+    /// # use kingler::sqlite::{OnDelete, Sqlite};
+    /// # fn main() -> Result<(), rusqlite::Error> {
+    /// # let path = std::env::temp_dir().join("kingler_sqlite_create_relationship_on_delete.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Sqlite::new(path.to_string_lossy().to_string())?;
+    /// db.connection().execute_batch("PRAGMA foreign_keys = ON")?;
+    /// # for table in ["author", "book"] {
+    /// #     db.create_table(table.to_string(), vec!["id INTEGER PRIMARY KEY".to_string()])?;
+    /// # }
+    /// db.create_relationship_on_delete(
+    ///     "author".to_string(), "book".to_string(), "id".to_string(), "id".to_string(),
+    ///     "ONE_TO_MANY".to_string(), Some(OnDelete::SetNull),
+    /// )?;
+    /// db.connection().execute_batch("INSERT INTO author (id) VALUES (1); INSERT INTO book (id, author_ref) VALUES (1, 1)")?;
+    /// db.connection().execute_batch("DELETE FROM author WHERE id = 1")?;
+    /// let author: Option<i64> = db.connection().query_row("SELECT author_ref FROM book", [], |row| row.get(0))?;
+    /// assert_eq!(author, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_relationship_on_delete(
+        &self,
+        table_name1: String,
+        table_name2: String,
+        column1: String,
+        column2: String,
+        relation_type: String,
+        on_delete: Option<OnDelete>
+    ) -> Result<RelationshipChange, rusqlite::Error> {
+        let action = on_delete.map_or(String::new(), |on_delete| format!(" {}", on_delete.sql()));
+        let quote = |name: &str| self.database().quote_ident(name);
         match relation_type.to_uppercase().as_str() {
             "MANY_TO_MANY" => {
                 // Create a junction table for many-to-many relationship
-                let junction_table = format!("{}_{}", table_name1.to_lowercase(), table_name2.to_lowercase());
+                let junction_table = Self::junction_table_name(&table_name1, &table_name2);
+                if self.table_exists(&junction_table)? {
+                    return Ok(RelationshipChange::Unchanged);
+                }
                 let query = format!(
-                    "CREATE TABLE IF NOT EXISTS {} ({}_ref INTEGER REFERENCES {}({}), {}_ref INTEGER REFERENCES {}({}))",
-                    junction_table,
-                    table_name1.to_lowercase(),
-                    table_name1,
-                    column1,
-                    table_name2.to_lowercase(),
-                    table_name2,
-                    column2
+                    "CREATE TABLE IF NOT EXISTS {} ({} INTEGER REFERENCES {}({}){}, {} INTEGER REFERENCES {}({}){})",
+                    quote(&junction_table),
+                    quote(&Self::reference_column_name(&table_name1)),
+                    quote(&table_name1),
+                    quote(&column1),
+                    action,
+                    quote(&Self::reference_column_name(&table_name2)),
+                    quote(&table_name2),
+                    quote(&column2),
+                    action
                 );
+                self.record(&query, &[]);
                 self.conn.execute(&query, [])?;
+                Ok(RelationshipChange::CreatedJunctionTable(junction_table))
             },
             "ONE_TO_MANY" => {
                 // Add foreign key to the "many" side
+                let ref_column = Self::reference_column_name(&table_name1);
+                if self.column_exists(&table_name2, &ref_column)? {
+                    return Ok(RelationshipChange::Unchanged);
+                }
                 let query = format!(
-                    "ALTER TABLE {} ADD COLUMN {}_ref INTEGER REFERENCES {}({})",
-                    table_name2, // The "many" side gets the foreign key
-                    table_name1.to_lowercase(),
-                    table_name1,
-                    column1
+                    "ALTER TABLE {} ADD COLUMN {} INTEGER REFERENCES {}({}){}",
+                    quote(&table_name2), // The "many" side gets the foreign key
+                    quote(&ref_column),
+                    quote(&table_name1),
+                    quote(&column1),
+                    action
                 );
+                self.record(&query, &[]);
                 self.conn.execute(&query, [])?;
+                Ok(RelationshipChange::AddedColumn { table: table_name2, column: ref_column })
             },
             "ONE_TO_ONE" => {
-                // Add foreign key with UNIQUE constraint
+                // Add foreign key, SQLite can't add a UNIQUE column so a unique index enforces it
+                let ref_column = Self::reference_column_name(&table_name2);
+                if self.column_exists(&table_name1, &ref_column)? {
+                    return Ok(RelationshipChange::Unchanged);
+                }
                 let query = format!(
-                    "ALTER TABLE {} ADD COLUMN {}_ref INTEGER UNIQUE REFERENCES {}({})",
-                    table_name1,
-                    table_name2.to_lowercase(),
-                    table_name2,
-                    column2
+                    "ALTER TABLE {} ADD COLUMN {} INTEGER REFERENCES {}({}){}",
+                    quote(&table_name1),
+                    quote(&ref_column),
+                    quote(&table_name2),
+                    quote(&column2),
+                    action
                 );
+                self.record(&query, &[]);
                 self.conn.execute(&query, [])?;
+                let query = format!(
+                    "CREATE UNIQUE INDEX IF NOT EXISTS {} ON {}({})",
+                    quote(&format!("{}_{}_unique", table_name1.to_lowercase(), ref_column)),
+                    quote(&table_name1),
+                    quote(&ref_column)
+                );
+                self.record(&query, &[]);
+                self.conn.execute(&query, [])?;
+                Ok(RelationshipChange::AddedColumn { table: table_name1, column: ref_column })
             },
-            _ => return Err(rusqlite::Error::ExecuteReturnedResults),
+            _ => Err(rusqlite::Error::ExecuteReturnedResults),
         }
+    }
+
+    /// Starts a transaction on the connection
+    pub fn begin(&self) -> Result<(), rusqlite::Error> {
+        self.conn.execute_batch("BEGIN")
+    }
+
+    /// Makes the connection read-only and starts a transaction reading from a
+    /// snapshot of the database
+    /// 
+    /// The snapshot is taken right away by reading the schema, so every query
+    /// of the transaction sees the database as it was when it began.
+    pub fn begin_read(&self) -> Result<(), rusqlite::Error> {
+        self.conn.execute_batch("PRAGMA query_only = ON; BEGIN DEFERRED; SELECT count(*) FROM sqlite_master")
+    }
+
+    /// Commits the current transaction
+    pub fn commit(&self) -> Result<(), rusqlite::Error> {
+        self.conn.execute_batch("COMMIT")
+    }
+
+    /// Rolls back the current transaction
+    pub fn rollback(&self) -> Result<(), rusqlite::Error> {
+        self.conn.execute_batch("ROLLBACK")
+    }
+
+    /// Rebuilds the database file to reclaim unused space
+    /// 
+    /// SQLite refuses to `VACUUM` inside a transaction.
+    pub fn vacuum(&self) -> Result<(), rusqlite::Error> {
+        self.conn.execute_batch("VACUUM")
+    }
 
+    /// Runs several statements separated by semicolons, binding no parameters
+    pub fn execute_script(&self, script: &str) -> Result<(), rusqlite::Error> {
+        self.record(script, &[]);
+        self.conn.execute_batch(script)
+    }
+
+    /// Updates the statistics used by the query planner
+    pub fn analyze(&self) -> Result<(), rusqlite::Error> {
+        self.conn.execute_batch("ANALYZE")
+    }
+
+    /// Copies the database into the file at `dest_path` with SQLite's online
+    /// backup API, replacing the file's contents
+    pub fn backup_to(&self, dest_path: &str) -> Result<(), rusqlite::Error> {
+        self.conn.backup(rusqlite::DatabaseName::Main, dest_path, None)
+    }
+
+    /// Replaces the database with a copy of the one in the file at `src_path`
+    pub fn restore_from(&mut self, src_path: &str) -> Result<(), rusqlite::Error> {
+        self.conn.restore(rusqlite::DatabaseName::Main, src_path, None::<fn(rusqlite::backup::Progress)>)
+    }
+
+    /// Checks that the database can be queried
+    /// 
+    /// `SELECT 1` alone never touches the file, so the query also reads the
+    /// schema table to catch files that aren't valid databases.
+    /// 
+    /// # Returns
+    /// * `Result<(), rusqlite::Error>` - Success (()) or a database error
+    pub fn ping(&self) -> Result<(), rusqlite::Error> {
+        self.conn.query_row("SELECT 1 FROM (SELECT count(*) FROM sqlite_master)", [], |row| row.get::<_, i64>(0))?;
         Ok(())
     }
-}
 
+    /// Runs `PRAGMA integrity_check` on the database
+    /// 
+    /// # Returns
+    /// * `Result<String, rusqlite::Error>` - The report of the check ("ok" for a healthy
+    ///   database, one problem per line otherwise) or a database error
+    pub fn integrity_check(&self) -> Result<String, rusqlite::Error> {
+        let mut stmt = self.conn.prepare("PRAGMA integrity_check")?;
+        let lines = stmt.query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+        Ok(lines.join("\n"))
+    }
+
+    /// Reads the page count, page size and free page count of the database
+    pub fn db_stats(&self) -> Result<DbStats, rusqlite::Error> {
+        let pragma = |name: &str| {
+            self.conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get::<_, i64>(0))
+                .map(|value| u64::try_from(value).unwrap_or(0))
+        };
+        Ok(DbStats {
+            page_count: pragma("page_count")?,
+            page_size: pragma("page_size")?,
+            freelist_count: pragma("freelist_count")?,
+        })
+    }
+}
@@ -0,0 +1,308 @@
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::hooks::Hooks;
+use crate::sqlite::Sqlite;
+use crate::{Kingler, KinglerError, KinglerValue, Table, TableNaming};
+
+/// A database transaction opened by [`Kingler::transaction`] or
+/// [`Kingler::read_transaction`]
+///
+/// Every operation run through the transaction uses its connection, so reads
+/// see the writes made earlier in the same transaction before they are committed.
+pub struct Transaction {
+    /// Connection the transaction was started on
+    pub(crate) sqlite: Sqlite,
+    /// How the table names are spelled, see [`KinglerBuilder::table_prefix`](crate::builder::KinglerBuilder::table_prefix)
+    /// and [`KinglerBuilder::lowercase_table_names`](crate::builder::KinglerBuilder::lowercase_table_names)
+    pub(crate) naming: TableNaming,
+    /// Callbacks of the handle the transaction was opened from
+    pub(crate) hooks: Arc<Hooks>,
+    /// Whether empty strings are written to text columns as NULL, see
+    /// [`KinglerBuilder::empty_string_as_null`](crate::builder::KinglerBuilder::empty_string_as_null)
+    pub(crate) empty_string_as_null: bool,
+}
+
+impl Transaction {
+    /// Creates the table of a struct as a temporary table, e.g. for scratch
+    /// computations
+    ///
+    /// Handles open a connection per operation, so a temporary table lives as
+    /// long as the transaction's connection: every operation of the transaction
+    /// sees it, and it is dropped when the transaction ends, whether it commits
+    /// or not. Other connections never see it. While it exists it hides a
+    /// persistent table of the same name.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Scratch {
+    ///     id: Option<i64>,
+    ///     total: f64,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_transaction_create_temp_table.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// let sum = db.transaction(|tx| {
+    ///     tx.create_temp_table(Scratch { id: None, total: 0.0 })?;
+    ///     tx.insert(&Scratch { id: None, total: 1.5 })?;
+    ///     tx.insert(&Scratch { id: None, total: 2.5 })?;
+    ///     let rows = Query::<Scratch>::new().fetch_in(tx)?;
+    ///     Ok(rows.iter().map(|row| row.total).sum::<f64>())
+    /// }).unwrap();
+    /// assert_eq!(sum, 4.0);
+    ///
+    /// // Gone with the transaction's connection
+    /// assert!(matches!(db.find_all::<Scratch>(), Err(KinglerError::TableNotFound(_))));
+    /// ```
+    pub fn create_temp_table<T: Table + Serialize>(&self, value: T) -> Result<(), KinglerError> {
+        let table_name = Kingler::table_name::<T>(&self.naming);
+        Kingler::create_table_with(&self.sqlite, &table_name, value, true)
+    }
+
+    /// Inserts a record within the transaction
+    ///
+    /// See [`Kingler::insert`].
+    pub fn insert<T: Table + Serialize>(&self, record: &T) -> Result<KinglerValue, KinglerError> {
+        Kingler::insert_with(&self.sqlite, &self.naming, &self.hooks, self.empty_string_as_null, record)
+    }
+
+    /// Finds the records matching the given equality filters within the transaction
+    ///
+    /// See [`Kingler::find_where`].
+    pub fn find_where<T: Table + DeserializeOwned>(
+        &self,
+        filters: &[(&str, KinglerValue)],
+    ) -> Result<Vec<T>, KinglerError> {
+        Kingler::find_where_with(&self.sqlite, &self.naming, filters)
+    }
+
+    /// Finds the record with the given primary key within the transaction
+    ///
+    /// See [`Kingler::find_by_id`].
+    pub fn find_by_id<T: Table + DeserializeOwned>(
+        &self,
+        id: impl Into<KinglerValue>,
+    ) -> Result<Option<T>, KinglerError> {
+        Kingler::find_by_id_with(&self.sqlite, &self.naming, id.into())
+    }
+
+    /// Updates a record within the transaction
+    ///
+    /// See [`Kingler::update`].
+    pub fn update<T: Table + Serialize>(&self, record: &T) -> Result<usize, KinglerError> {
+        Kingler::update_with(&self.sqlite, &self.naming, &self.hooks, self.empty_string_as_null, record)
+    }
+
+    /// Deletes the record with the given primary key within the transaction
+    ///
+    /// See [`Kingler::delete_by_id`].
+    pub fn delete_by_id<T: Table>(&self, id: impl Into<KinglerValue>) -> Result<usize, KinglerError> {
+        Kingler::delete_by_id_with::<T>(&self.sqlite, &self.naming, &self.hooks, id.into())
+    }
+}
+
+impl Kingler {
+    /// Runs a closure inside a database transaction
+    ///
+    /// The transaction is committed if the closure returns `Ok` and rolled back
+    /// if it returns an error, which is then returned unchanged. Other operations
+    /// on `self` use their own connection, so they only see the changes once
+    /// committed and writing from them blocks until the transaction ends.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_transaction.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    ///
+    /// db.transaction(|tx| {
+    ///     tx.insert(&Client { id: None, name: "John Doe".to_string() })?;
+    ///     tx.insert(&Client { id: None, name: "Jane Doe".to_string() })
+    /// }).unwrap();
+    /// assert_eq!(db.find_all::<Client>().unwrap().len(), 2);
+    ///
+    /// // A failing closure rolls everything back
+    /// let result: Result<(), _> = db.transaction(|tx| {
+    ///     tx.insert(&Client { id: None, name: "Richard Roe".to_string() })?;
+    ///     Err(kingler::KinglerError::UnknownColumn("oops".to_string()))
+    /// });
+    /// assert!(result.is_err());
+    /// assert_eq!(db.find_all::<Client>().unwrap().len(), 2);
+    /// ```
+    pub fn transaction<F, R>(&self, f: F) -> Result<R, KinglerError>
+    where
+        F: FnOnce(&Transaction) -> Result<R, KinglerError>,
+    {
+        let tx = Transaction {
+            sqlite: self.connect()?,
+            naming: self.naming.clone(),
+            hooks: self.hooks.clone(),
+            empty_string_as_null: self.empty_string_as_null,
+        };
+        tx.sqlite.begin()?;
+        match f(&tx) {
+            Ok(result) => {
+                tx.sqlite.commit()?;
+                Ok(result)
+            }
+            Err(err) => {
+                tx.sqlite.rollback()?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Runs a closure inside a database transaction like [`Kingler::transaction`],
+    /// running it again in a new transaction when the database is busy or locked
+    ///
+    /// A transaction failing because another connection holds a conflicting
+    /// lock is rolled back, so retrying has to run the whole closure again. Each
+    /// attempt already waits for the lock up to SQLite's busy timeout before
+    /// failing. At most `max_attempts` attempts are made, at least one, and the
+    /// error of the last one is returned. Any other error is returned at once.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_transaction_with_retry.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    ///
+    /// let busy = || KinglerError::Sqlite(rusqlite::Error::SqliteFailure(
+    ///     rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+    ///     None,
+    /// ));
+    /// let mut attempts = 0;
+    /// db.transaction_with_retry(3, |tx| {
+    ///     attempts += 1;
+    ///     tx.insert(&Client { id: None, name: "John Doe".to_string() })?;
+    ///     // The first attempt loses to a concurrent writer
+    ///     if attempts == 1 {
+    ///         return Err(busy());
+    ///     }
+    ///     Ok(())
+    /// }).unwrap();
+    /// assert_eq!(attempts, 2);
+    /// assert_eq!(db.find_all::<Client>().unwrap().len(), 1);
+    ///
+    /// let mut attempts = 0;
+    /// let result: Result<(), _> = db.transaction_with_retry(3, |_| {
+    ///     attempts += 1;
+    ///     Err(busy())
+    /// });
+    /// assert!(matches!(result, Err(KinglerError::Sqlite(_))));
+    /// assert_eq!(attempts, 3);
+    /// ```
+    pub fn transaction_with_retry<F, R>(&self, max_attempts: usize, mut f: F) -> Result<R, KinglerError>
+    where
+        F: FnMut(&Transaction) -> Result<R, KinglerError>,
+    {
+        let mut attempt = 1;
+        loop {
+            match self.transaction(&mut f) {
+                Err(err) if attempt < max_attempts && Self::is_busy(&err) => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+
+    /// Internal helper function that tells whether an error comes from another
+    /// connection holding a conflicting lock
+    fn is_busy(err: &KinglerError) -> bool {
+        let KinglerError::Sqlite(err) = err else {
+            return false;
+        };
+        matches!(
+            err.sqlite_error_code(),
+            Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+        )
+    }
+
+    /// Runs a closure inside a read-only transaction
+    ///
+    /// Every query of the closure reads from the same snapshot of the database,
+    /// so reports spanning several queries are consistent even while other
+    /// connections write. Reads are routed like other reads (see
+    /// [`Kingler::with_read_write`]) and writes inside the closure fail with a
+    /// `KinglerError::Sqlite` error. Changes committed by other connections
+    /// meanwhile are only seen once the transaction ends, provided the database
+    /// is in WAL mode: otherwise writers wait for it to end.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Client {
+    ///     id: Option<i64>,
+    ///     name: String,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_read_transaction.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// # let _ = std::fs::remove_file(path.with_extension("db-wal"));
+    /// # let _ = std::fs::remove_file(path.with_extension("db-shm"));
+    /// # let conn = rusqlite::Connection::open(&path).unwrap();
+    /// # conn.pragma_update(None, "journal_mode", "WAL").unwrap();
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Client { id: None, name: String::new() }).unwrap();
+    /// db.insert(&Client { id: None, name: "John Doe".to_string() }).unwrap();
+    ///
+    /// let (before, after) = db.read_transaction(|tx| {
+    ///     let before = tx.find_where::<Client>(&[])?.len();
+    ///     // Committed by another connection while the transaction is open
+    ///     db.insert(&Client { id: None, name: "Jane Doe".to_string() })?;
+    ///     let after = tx.find_where::<Client>(&[])?.len();
+    ///
+    ///     assert!(tx.insert(&Client { id: None, name: "Richard Roe".to_string() }).is_err());
+    ///     Ok((before, after))
+    /// }).unwrap();
+    /// assert_eq!((before, after), (1, 1));
+    /// assert_eq!(db.find_all::<Client>().unwrap().len(), 2);
+    /// ```
+    pub fn read_transaction<F, R>(&self, f: F) -> Result<R, KinglerError>
+    where
+        F: FnOnce(&Transaction) -> Result<R, KinglerError>,
+    {
+        let tx = Transaction {
+            sqlite: self.connect_read()?,
+            naming: self.naming.clone(),
+            hooks: self.hooks.clone(),
+            empty_string_as_null: self.empty_string_as_null,
+        };
+        tx.sqlite.begin_read()?;
+        match f(&tx) {
+            Ok(result) => {
+                tx.sqlite.commit()?;
+                Ok(result)
+            }
+            Err(err) => {
+                tx.sqlite.rollback()?;
+                Err(err)
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use serde::Serialize;
+
+use crate::{Kingler, KinglerError, KinglerValue, Table};
+
+/// Checks a record before it is written
+///
+/// Implemented by structs with rules the database schema can't express.
+/// [`Kingler::insert_validated`] and [`Kingler::update_validated`] run the
+/// check and only touch the database when it passes.
+///
+/// # Example
+/// ```rust
+/// # use kingler::{Kingler, KinglerError, Table, Validate};
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize, Table)]
+/// struct Person {
+///     id: Option<i64>,
+///     name: String,
+///     age: i64,
+/// }
+///
+/// impl Validate for Person {
+///     fn validate(&self) -> Result<(), KinglerError> {
+///         if self.age < 0 {
+///             return Err(KinglerError::Validation("age can't be negative".to_string()));
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// # let path = std::env::temp_dir().join("kingler_validate.db");
+/// # let _ = std::fs::remove_file(&path);
+/// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+/// db.create_table(Person { id: None, name: String::new(), age: 0 }).unwrap();
+///
+/// let result = db.insert_validated(&Person { id: None, name: "John Doe".to_string(), age: -1 });
+/// assert!(matches!(result, Err(KinglerError::Validation(message)) if message == "age can't be negative"));
+/// assert!(db.find_all::<Person>().unwrap().is_empty());
+///
+/// db.insert_validated(&Person { id: None, name: "John Doe".to_string(), age: 25 }).unwrap();
+/// assert_eq!(db.find_all::<Person>().unwrap().len(), 1);
+/// ```
+pub trait Validate {
+    /// Returns `KinglerError::Validation` describing the first rule the record
+    /// breaks, if any
+    fn validate(&self) -> Result<(), KinglerError>;
+}
+
+impl Kingler {
+    /// Validates a record and inserts it if it passes
+    ///
+    /// See [`Kingler::insert`] for what is returned.
+    ///
+    /// # Errors
+    /// Returns the error of [`Validate::validate`] without opening a connection
+    /// when the record is invalid.
+    pub fn insert_validated<T: Table + Serialize + Validate>(&self, record: &T) -> Result<KinglerValue, KinglerError> {
+        record.validate()?;
+        self.insert(record)
+    }
+
+    /// Validates a record and updates it if it passes
+    ///
+    /// See [`Kingler::update`] for what is returned.
+    ///
+    /// # Errors
+    /// Returns the error of [`Validate::validate`] without opening a connection
+    /// when the record is invalid.
+    pub fn update_validated<T: Table + Serialize + Validate>(&self, record: &T) -> Result<usize, KinglerError> {
+        record.validate()?;
+        self.update(record)
+    }
+}
@@ -0,0 +1,287 @@
+use rusqlite::types::{ToSqlOutput, Value, ValueRef};
+
+/// A value bound to a SQL statement parameter
+///
+/// # Example
+/// ```rust
+/// use kingler::KinglerValue;
+///
+/// assert_eq!(KinglerValue::from("John"), KinglerValue::Text("John".to_string()));
+/// assert_eq!(KinglerValue::from(25), KinglerValue::Int(25));
+/// ```
+///
+/// Reals are bound as SQLite REAL values and never go through text, so they
+/// read back bit for bit:
+/// ```rust
+/// # use kingler::{Kingler, Table};
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize, Table)]
+/// struct Measurement {
+///     id: Option<i64>,
+///     value: f64,
+/// }
+///
+/// # let path = std::env::temp_dir().join("kingler_value_real_round_trip.db");
+/// # let _ = std::fs::remove_file(&path);
+/// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+/// db.create_table(Measurement { id: None, value: 0.0 }).unwrap();
+///
+/// let value = 0.1 + 0.2;
+/// let id = db.insert(&Measurement { id: None, value }).unwrap();
+/// let stored = db.find_by_id::<Measurement>(id).unwrap().unwrap();
+/// assert_eq!(stored.value, value);
+/// assert_eq!(stored.value.to_bits(), 0.30000000000000004f64.to_bits());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum KinglerValue {
+    Null,
+    Int(i64),
+    Real(f64),
+    Text(String),
+    Bool(bool),
+    Blob(Vec<u8>),
+}
+
+impl KinglerValue {
+    /// The integer held by the value, such as the key returned by
+    /// [`Kingler::insert`](crate::Kingler::insert) for an integer primary key
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            KinglerValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// The date and time held by the value, parsed from RFC 3339 text such as
+    /// `2024-03-01T09:30:15+01:00`, or `None` for other values
+    ///
+    /// Reads back the values of datetime columns, e.g. from
+    /// [`Kingler::find_all_dynamic`](crate::Kingler::find_all_dynamic). Requires
+    /// the `time` feature.
+    #[cfg(feature = "time")]
+    pub fn as_offset_date_time(&self) -> Option<time::OffsetDateTime> {
+        match self {
+            KinglerValue::Text(text) => {
+                time::OffsetDateTime::parse(text, &time::format_description::well_known::Rfc3339).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<&serde_json::Value> for KinglerValue {
+    /// Converts a serialized field into the value bound for its column
+    ///
+    /// Integers are kept exact: those fitting an `i64` are bound as integers and
+    /// larger ones, such as a `u64` above `i64::MAX`, as their decimal text.
+    /// Other numbers are bound as reals, while numbers out of the range of
+    /// `f64`, which only arbitrary precision JSON can hold, are bound as their
+    /// decimal text too. SQLite would turn such text into an inexact REAL in an
+    /// INTEGER column, so writing it to one of a `#[derive(Table)]` struct fails
+    /// with `KinglerError::IntegerOutOfRange` instead; store such values in an
+    /// `i128` or `u128` field, see [`Column::big_integer`](crate::Column::big_integer).
+    ///
+    /// # Example
+    /// ```rust
+    /// use kingler::KinglerValue;
+    ///
+    /// let value = |json: &str| KinglerValue::from(&serde_json::from_str::<serde_json::Value>(json).unwrap());
+    /// assert_eq!(value("-7"), KinglerValue::Int(-7));
+    /// assert_eq!(value("18446744073709551615"), KinglerValue::Text("18446744073709551615".to_string()));
+    /// assert_eq!(value("0.5"), KinglerValue::Real(0.5));
+    /// assert!(matches!(value("1e400"), KinglerValue::Text(text) if text.ends_with("400")));
+    /// assert_eq!(
+    ///     value("340282366920938463463374607431768211456"),
+    ///     KinglerValue::Text("340282366920938463463374607431768211456".to_string())
+    /// );
+    /// ```
+    ///
+    /// A `u64` field then only stores values up to `i64::MAX`:
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerError, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Counter {
+    ///     id: Option<i64>,
+    ///     hits: u64,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_value_u64_out_of_range.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// db.create_table(Counter { id: None, hits: 0 }).unwrap();
+    ///
+    /// let id = db.insert(&Counter { id: None, hits: i64::MAX as u64 }).unwrap();
+    /// assert_eq!(db.find_by_id::<Counter>(id).unwrap().unwrap().hits, i64::MAX as u64);
+    ///
+    /// let result = db.insert(&Counter { id: None, hits: u64::MAX });
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(KinglerError::IntegerOutOfRange { column, value }) if column == "hits" && value == u64::MAX.to_string()
+    /// ));
+    /// assert_eq!(db.find_all::<Counter>().unwrap().len(), 1);
+    /// ```
+    fn from(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => KinglerValue::Null,
+            serde_json::Value::Bool(b) => KinglerValue::Bool(*b),
+            serde_json::Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    KinglerValue::Int(i)
+                } else if !n.to_string().contains(['.', 'e', 'E']) {
+                    // An integer beyond i64
+                    KinglerValue::Text(n.to_string())
+                } else if let Some(f) = n.as_f64().filter(|f| f.is_finite()) {
+                    KinglerValue::Real(f)
+                } else {
+                    KinglerValue::Text(n.to_string())
+                }
+            }
+            serde_json::Value::String(s) => KinglerValue::Text(s.clone()),
+            _ => KinglerValue::Text(value.to_string()),
+        }
+    }
+}
+
+impl From<&KinglerValue> for serde_json::Value {
+    /// Converts a value into JSON, with blobs as arrays of bytes and non-finite
+    /// reals as null
+    fn from(value: &KinglerValue) -> Self {
+        match value {
+            KinglerValue::Null => serde_json::Value::Null,
+            KinglerValue::Int(i) => serde_json::Value::from(*i),
+            KinglerValue::Real(f) => serde_json::Number::from_f64(*f)
+                .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            KinglerValue::Text(s) => serde_json::Value::String(s.clone()),
+            KinglerValue::Bool(b) => serde_json::Value::Bool(*b),
+            KinglerValue::Blob(b) => serde_json::Value::from(b.clone()),
+        }
+    }
+}
+
+impl From<&str> for KinglerValue {
+    fn from(value: &str) -> Self {
+        KinglerValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for KinglerValue {
+    fn from(value: String) -> Self {
+        KinglerValue::Text(value)
+    }
+}
+
+impl From<i64> for KinglerValue {
+    fn from(value: i64) -> Self {
+        KinglerValue::Int(value)
+    }
+}
+
+impl From<i32> for KinglerValue {
+    fn from(value: i32) -> Self {
+        KinglerValue::Int(value as i64)
+    }
+}
+
+impl From<f64> for KinglerValue {
+    fn from(value: f64) -> Self {
+        KinglerValue::Real(value)
+    }
+}
+
+impl From<bool> for KinglerValue {
+    fn from(value: bool) -> Self {
+        KinglerValue::Bool(value)
+    }
+}
+
+impl From<Vec<u8>> for KinglerValue {
+    fn from(value: Vec<u8>) -> Self {
+        KinglerValue::Blob(value)
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<time::OffsetDateTime> for KinglerValue {
+    /// Converts a date and time into RFC 3339 text, keeping its offset, which is
+    /// how `#[serde(with = "time::serde::rfc3339")]` stores the fields of a record
+    /// so the value can be compared with them
+    ///
+    /// RFC 3339 only spells the years 0 to 9999 and whole-minute offsets, other
+    /// values are bound in the `time` crate's display format instead. Requires
+    /// the `time` feature.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use kingler::{Kingler, KinglerValue, Query, Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// use time::format_description::well_known::Rfc3339;
+    /// use time::OffsetDateTime;
+    ///
+    /// #[derive(Serialize, Deserialize, Table)]
+    /// struct Event {
+    ///     id: Option<i64>,
+    ///     name: String,
+    ///     #[serde(with = "time::serde::rfc3339")]
+    ///     starts_at: OffsetDateTime,
+    /// }
+    ///
+    /// # let path = std::env::temp_dir().join("kingler_value_offset_date_time.db");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let db = Kingler::new("sqlite".to_string(), path.to_string_lossy().to_string());
+    /// let starts_at = OffsetDateTime::parse("2024-03-01T09:30:15.25+01:00", &Rfc3339).unwrap();
+    /// db.create_table(Event { id: None, name: String::new(), starts_at }).unwrap();
+    /// let id = db.insert(&Event { id: None, name: "Launch".to_string(), starts_at }).unwrap();
+    ///
+    /// let stored = db.find_by_id::<Event>(id).unwrap().unwrap();
+    /// assert_eq!(stored.starts_at, starts_at);
+    /// assert_eq!(stored.starts_at.offset(), starts_at.offset());
+    ///
+    /// let value = KinglerValue::from(starts_at);
+    /// assert_eq!(value, KinglerValue::from("2024-03-01T09:30:15.25+01:00"));
+    /// assert_eq!(value.as_offset_date_time(), Some(starts_at));
+    /// let events = Query::<Event>::new().filter("starts_at", starts_at).fetch(&db).unwrap();
+    /// assert_eq!(events[0].name, "Launch");
+    /// ```
+    fn from(value: time::OffsetDateTime) -> Self {
+        let text = value
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_else(|_| value.to_string());
+        KinglerValue::Text(text)
+    }
+}
+
+impl<T: Into<KinglerValue>> From<Option<T>> for KinglerValue {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => KinglerValue::Null,
+        }
+    }
+}
+
+impl From<ValueRef<'_>> for KinglerValue {
+    /// Converts a value read from SQLite
+    fn from(value: ValueRef<'_>) -> Self {
+        match value {
+            ValueRef::Null => KinglerValue::Null,
+            ValueRef::Integer(i) => KinglerValue::Int(i),
+            ValueRef::Real(f) => KinglerValue::Real(f),
+            ValueRef::Text(t) => KinglerValue::Text(String::from_utf8_lossy(t).into_owned()),
+            ValueRef::Blob(b) => KinglerValue::Blob(b.to_vec()),
+        }
+    }
+}
+
+impl rusqlite::ToSql for KinglerValue {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(match self {
+            KinglerValue::Null => ToSqlOutput::Owned(Value::Null),
+            KinglerValue::Int(i) => ToSqlOutput::Owned(Value::Integer(*i)),
+            KinglerValue::Real(f) => ToSqlOutput::Owned(Value::Real(*f)),
+            KinglerValue::Text(s) => ToSqlOutput::Borrowed(ValueRef::Text(s.as_bytes())),
+            KinglerValue::Bool(b) => ToSqlOutput::Owned(Value::Integer(*b as i64)),
+            KinglerValue::Blob(b) => ToSqlOutput::Borrowed(ValueRef::Blob(b)),
+        })
+    }
+}